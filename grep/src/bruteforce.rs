@@ -1,11 +1,16 @@
 pub fn bruteforce(text: &str, pattern: &str) -> bool {
     let text_chars: Vec<char> = text.chars().collect();
     let pattern_chars: Vec<char> = pattern.chars().collect();
+    bruteforce_position(&text_chars, &pattern_chars).is_some()
+}
+
+/** Returns the index of the first char where `pattern` occurs in `text`. */
+pub fn bruteforce_position(text_chars: &[char], pattern_chars: &[char]) -> Option<usize> {
     let n = text_chars.len();
     let m = pattern_chars.len();
 
     if m > n {
-        return false;
+        return None;
     }
 
     for i in 0..=n - m {
@@ -14,8 +19,8 @@ pub fn bruteforce(text: &str, pattern: &str) -> bool {
             j += 1;
         }
         if j == m {
-            return true;
+            return Some(i);
         }
     }
-    false
+    None
 }
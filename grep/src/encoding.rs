@@ -0,0 +1,171 @@
+use std::io::{self, BufRead, Read};
+
+/** Text encodings a source can be transcoded from before matching. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+}
+
+impl Encoding {
+    /** Parses names like `utf-8`, `utf-16le` or `latin1`, ignoring case. */
+    pub fn from_name(name: &str) -> Option<Encoding> {
+        match name.to_ascii_lowercase().as_str() {
+            "utf-8" | "utf8" => Some(Encoding::Utf8),
+            "utf-16le" | "utf16le" => Some(Encoding::Utf16Le),
+            "utf-16be" | "utf16be" => Some(Encoding::Utf16Be),
+            "latin1" | "latin-1" | "iso-8859-1" => Some(Encoding::Latin1),
+            _ => None,
+        }
+    }
+
+    /** The byte order mark that may start a source in this encoding. */
+    pub fn bom(&self) -> &'static [u8] {
+        match self {
+            Encoding::Utf8 => &[0xEF, 0xBB, 0xBF],
+            Encoding::Utf16Le => &[0xFF, 0xFE],
+            Encoding::Utf16Be => &[0xFE, 0xFF],
+            Encoding::Latin1 => &[],
+        }
+    }
+
+    /** Guesses the encoding from a byte order mark, falling back to UTF-8. */
+    pub fn detect(start: &[u8]) -> Encoding {
+        [Encoding::Utf8, Encoding::Utf16Le, Encoding::Utf16Be]
+            .into_iter()
+            .find(|encoding| start.starts_with(encoding.bom()))
+            .unwrap_or(Encoding::Utf8)
+    }
+}
+
+/** Wraps a reader over `encoding` text and yields the same text as UTF-8.
+Invalid sequences become U+FFFD. */
+pub struct DecodingReader<R> {
+    inner: R,
+    encoding: Encoding,
+    /** Undecoded bytes left over from the previous read, like half a UTF-16 unit. */
+    carry: Vec<u8>,
+    /** Decoded UTF-8 waiting to be handed out. */
+    decoded: Vec<u8>,
+    position: usize,
+}
+
+impl<R: BufRead> DecodingReader<R> {
+    pub fn new(inner: R, encoding: Encoding) -> DecodingReader<R> {
+        DecodingReader {
+            inner,
+            encoding,
+            carry: Vec::new(),
+            decoded: Vec::new(),
+            position: 0,
+        }
+    }
+
+    /** Decodes the next chunk of input into `decoded`. Returns false at end of input. */
+    fn decode_next(&mut self) -> io::Result<bool> {
+        let input = self.inner.fill_buf()?;
+        let at_end = input.is_empty();
+        self.carry.extend_from_slice(input);
+        let consumed = input.len();
+        self.inner.consume(consumed);
+
+        self.decoded.clear();
+        self.position = 0;
+        let mut text = String::new();
+        match self.encoding {
+            Encoding::Utf8 => loop {
+                match std::str::from_utf8(&self.carry) {
+                    Ok(valid) => {
+                        text.push_str(valid);
+                        self.carry.clear();
+                        break;
+                    }
+                    Err(error) => {
+                        let valid = error.valid_up_to();
+                        text.push_str(std::str::from_utf8(&self.carry[..valid]).unwrap());
+                        match error.error_len() {
+                            Some(invalid) => {
+                                text.push(char::REPLACEMENT_CHARACTER);
+                                self.carry.drain(..valid + invalid);
+                            }
+                            // The sequence may be completed by the next read
+                            None if !at_end => {
+                                self.carry.drain(..valid);
+                                break;
+                            }
+                            None => {
+                                text.push(char::REPLACEMENT_CHARACTER);
+                                self.carry.clear();
+                                break;
+                            }
+                        }
+                    }
+                }
+            },
+            Encoding::Latin1 => {
+                text.extend(self.carry.drain(..).map(char::from));
+            }
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let mut units: Vec<u16> = self
+                    .carry
+                    .chunks_exact(2)
+                    .map(|pair| match self.encoding {
+                        Encoding::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+                        _ => u16::from_be_bytes([pair[0], pair[1]]),
+                    })
+                    .collect();
+                let mut rest = self.carry[units.len() * 2..].to_vec();
+                // A high surrogate may be waiting for its pair in the next read
+                if !at_end
+                    && units
+                        .last()
+                        .is_some_and(|unit| (0xD800..0xDC00).contains(unit))
+                {
+                    let unit = units.pop().unwrap();
+                    let bytes = match self.encoding {
+                        Encoding::Utf16Le => unit.to_le_bytes(),
+                        _ => unit.to_be_bytes(),
+                    };
+                    rest.splice(0..0, bytes);
+                }
+                text.extend(
+                    char::decode_utf16(units).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)),
+                );
+                if at_end && !rest.is_empty() {
+                    text.push(char::REPLACEMENT_CHARACTER);
+                    rest.clear();
+                }
+                self.carry = rest;
+            }
+        }
+        self.decoded.extend_from_slice(text.as_bytes());
+        Ok(!at_end)
+    }
+}
+
+impl<R: BufRead> Read for DecodingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let amount = available.len().min(buf.len());
+        buf[..amount].copy_from_slice(&available[..amount]);
+        self.consume(amount);
+        Ok(amount)
+    }
+}
+
+impl<R: BufRead> BufRead for DecodingReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        while self.position >= self.decoded.len() {
+            if !self.decode_next()? && self.decoded.is_empty() {
+                break;
+            }
+        }
+        Ok(&self.decoded[self.position..])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.position = (self.position + amount).min(self.decoded.len());
+    }
+}
@@ -1,6 +1,30 @@
 pub mod bruteforce;
+pub mod encoding;
+pub mod matcher;
+pub mod output;
+pub mod report;
 pub mod search;
+pub mod source;
 
-pub trait SearchStrategy {
-    fn search(&self, file_paths: &[String], pattern: &str) -> usize;
+use matcher::{MatchOptions, Matcher};
+use output::{LineOutput, Output};
+use report::SearchReport;
+use source::Source;
+
+pub trait SearchStrategy: Send + Sync {
+    fn search_report(
+        &self,
+        sources: Vec<Source>,
+        matcher: &Matcher,
+        output: &dyn Output,
+    ) -> SearchReport;
+
+    fn search_sources(&self, sources: Vec<Source>, pattern: &str) -> usize {
+        let matcher = Matcher::new(pattern, MatchOptions::default());
+        self.search_report(sources, &matcher, &LineOutput).matches()
+    }
+
+    fn search(&self, file_paths: &[String], pattern: &str) -> usize {
+        self.search_sources(file_paths.iter().map(Source::path).collect(), pattern)
+    }
 }
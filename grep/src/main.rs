@@ -1,28 +1,95 @@
 use grep::SearchStrategy;
-use grep::search::{ChunkedConcurrentSearch, ConcurrentSearch, SequentialSearch};
-use std::env;
+use grep::encoding::Encoding;
+use grep::matcher::{MatchOptions, Matcher, Normalization};
+use grep::output::{HeadingOutput, LineOutput, Output};
+use grep::search::{AutoSearch, strategy_from_mode};
+use grep::source::Source;
+use std::{env, process};
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    if args.len() < 3 {
-        eprintln!("Uso: {} <modo> <patrón> <archivo1> [archivo2 ...]", args[0]);
+    let mut stats = false;
+    let mut debug = false;
+    let mut heading = false;
+    let mut encoding = None;
+    let mut options = MatchOptions::default();
+    let mut positional = vec![];
+    for arg in &args[1..] {
+        match arg.as_str() {
+            "--stats" => stats = true,
+            "--debug" => debug = true,
+            "--heading" => heading = true,
+            "-i" | "--ignore-case" => options.ignore_case = true,
+            "--nfc" => options.normalization = Normalization::Nfc,
+            "--nfd" => options.normalization = Normalization::Nfd,
+            _ if arg.starts_with("--encoding=") => {
+                let name = &arg["--encoding=".len()..];
+                encoding = match Encoding::from_name(name) {
+                    Some(parsed) => Some(parsed),
+                    None if name == "auto" => None,
+                    None => {
+                        eprintln!(
+                            "Codificación no válida. Usa 'auto', 'utf-8', 'utf-16le', 'utf-16be' o 'latin1'."
+                        );
+                        return;
+                    }
+                };
+            }
+            _ => positional.push(arg),
+        }
+    }
+    if positional.len() < 3 {
+        eprintln!(
+            "Uso: {} [--stats] [--debug] [--heading] [--encoding=<codificación>] [-i] [--nfc|--nfd] <modo> <patrón> <archivo1> [archivo2 ...]",
+            args[0]
+        );
         return;
     }
 
-    let mode = &args[1];
-    let pattern = &args[2];
-    let files = &args[3..];
+    let mode = positional[0];
+    let pattern = positional[1];
+    let files = &positional[2..];
 
-    let search_strategy: Box<dyn SearchStrategy> = match mode.as_str() {
-        "seq" => Box::new(SequentialSearch),
-        "conc" => Box::new(ConcurrentSearch),
-        "c-chunk" => Box::new(ChunkedConcurrentSearch { chunk_size: 1024 }), // 1 KB por chunk
-        _ => {
-            eprintln!("Modo no válido. Usa 'seq', 'conc' o 'c-chunk'.");
+    let search_strategy: Box<dyn SearchStrategy> = if mode == "auto" {
+        Box::new(AutoSearch {
+            debug,
+            ..AutoSearch::new()
+        })
+    } else {
+        let Some(search_strategy) = strategy_from_mode(mode) else {
+            eprintln!("Modo no válido. Usa 'seq', 'conc', 'c-chunk', 'pipe' o 'auto'.");
             return;
-        }
+        };
+        search_strategy
     };
 
-    let file_list: Vec<String> = files.iter().map(|s| s.to_string()).collect();
-    search_strategy.search(&file_list, pattern);
+    let matcher = Matcher::new(pattern, options);
+    let sources: Vec<Source> = files
+        .iter()
+        .map(|s| {
+            let source = Source::path(s.as_str());
+            match encoding {
+                Some(encoding) => source.with_encoding(encoding),
+                None => source,
+            }
+        })
+        .collect();
+    let output: Box<dyn Output> = if heading {
+        Box::new(HeadingOutput::new())
+    } else {
+        Box::new(LineOutput)
+    };
+    let report = search_strategy.search_report(sources, &matcher, output.as_ref());
+    if heading {
+        println!("Total: {} matches", report.matches());
+    }
+    if stats {
+        eprintln!("{}", report.as_string());
+    }
+    for failed in &report.errors {
+        eprintln!("Error al leer {}: {}", failed.name, failed.error);
+    }
+    if !report.errors.is_empty() {
+        process::exit(2);
+    }
 }
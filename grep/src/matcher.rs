@@ -0,0 +1,213 @@
+use std::ops::Range;
+
+use caseless::Caseless;
+use unicode_normalization::char::canonical_combining_class;
+use unicode_normalization::{IsNormalized, UnicodeNormalization, is_nfc_quick};
+
+use crate::bruteforce::{bruteforce, bruteforce_position};
+
+/** Unicode normalization form applied to both the pattern and the text before comparing. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Normalization {
+    #[default]
+    None,
+    Nfc,
+    Nfd,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct MatchOptions {
+    pub ignore_case: bool,
+    pub normalization: Normalization,
+}
+
+/** A pattern prepared for matching under a set of options.
+Case-insensitive matching uses Unicode full case folding, so `Straße` matches `STRASSE`. */
+pub struct Matcher {
+    pattern: String,
+    prepared: Vec<char>,
+    options: MatchOptions,
+}
+
+impl Matcher {
+    pub fn new(pattern: &str, options: MatchOptions) -> Matcher {
+        let prepared = Self::prepare(pattern, options)
+            .into_iter()
+            .map(|(c, _)| c)
+            .collect();
+        Matcher {
+            pattern: pattern.to_string(),
+            prepared,
+            options,
+        }
+    }
+
+    /** The pattern exactly as it was given. */
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    pub fn options(&self) -> MatchOptions {
+        self.options
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        if self.options == MatchOptions::default() {
+            return bruteforce(text, &self.pattern);
+        }
+        self.find(text).is_some()
+    }
+
+    /** Finds the first match, returning its byte range in the original, untransformed text. */
+    pub fn find(&self, text: &str) -> Option<Range<usize>> {
+        if self.options == MatchOptions::default() {
+            let offsets: Vec<(usize, char)> = text.char_indices().collect();
+            let chars: Vec<char> = offsets.iter().map(|(_, c)| *c).collect();
+            let start = bruteforce_position(&chars, &self.prepared)?;
+            let end = start + self.prepared.len();
+            let byte_at = |i: usize| offsets.get(i).map_or(text.len(), |(offset, _)| *offset);
+            return Some(byte_at(start)..byte_at(end));
+        }
+        let prepared = Self::prepare(text, self.options);
+        let chars: Vec<char> = prepared.iter().map(|(c, _)| *c).collect();
+        let start = bruteforce_position(&chars, &self.prepared)?;
+        if self.prepared.is_empty() {
+            let offset = prepared
+                .get(start)
+                .map_or(text.len(), |(_, span)| span.start);
+            return Some(offset..offset);
+        }
+        let end = start + self.prepared.len() - 1;
+        Some(prepared[start].1.start..prepared[end].1.end)
+    }
+
+    /** Transforms `text` according to `options`, tagging every resulting char with the
+    byte range of the original text it came from. */
+    fn prepare(text: &str, options: MatchOptions) -> Vec<(char, Range<usize>)> {
+        let spans = match options.normalization {
+            Normalization::None => text
+                .char_indices()
+                .map(|(i, c)| i..i + c.len_utf8())
+                .collect(),
+            // Chars that may compose with each other are normalized together
+            _ => Self::segments(text),
+        };
+        let mut prepared = Vec::with_capacity(text.len());
+        for span in spans {
+            let segment = &text[span.clone()];
+            let chars: Box<dyn Iterator<Item = char>> =
+                match (options.ignore_case, options.normalization) {
+                    (false, Normalization::None) => Box::new(segment.chars()),
+                    (true, Normalization::None) => Box::new(segment.chars().default_case_fold()),
+                    (false, Normalization::Nfd) => Box::new(segment.nfd()),
+                    (true, Normalization::Nfd) => Box::new(segment.nfd().default_case_fold().nfd()),
+                    (false, Normalization::Nfc) => Box::new(segment.nfc()),
+                    (true, Normalization::Nfc) => Box::new(segment.nfd().default_case_fold().nfc()),
+                };
+            prepared.extend(chars.map(|c| (c, span.clone())));
+        }
+        prepared
+    }
+
+    /** Splits `text` into runs that normalize independently of each other. A run starts at
+    a starter that can never compose with what precedes it, so combining marks stay with
+    their base, and Hangul vowels and trailing consonants with their leading jamo. */
+    fn segments(text: &str) -> Vec<Range<usize>> {
+        let mut starts: Vec<usize> = text
+            .char_indices()
+            .filter(|(i, c)| {
+                *i == 0
+                    || (canonical_combining_class(*c) == 0
+                        && is_nfc_quick(std::iter::once(*c)) != IsNormalized::Maybe)
+            })
+            .map(|(i, _)| i)
+            .collect();
+        starts.push(text.len());
+        starts.windows(2).map(|w| w[0]..w[1]).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(
+        pattern: &str,
+        text: &str,
+        ignore_case: bool,
+        normalization: Normalization,
+    ) -> Option<Range<usize>> {
+        let options = MatchOptions {
+            ignore_case,
+            normalization,
+        };
+        Matcher::new(pattern, options).find(text)
+    }
+
+    #[test]
+    fn full_case_folding_maps_spans_back_to_the_original_text() {
+        assert_eq!(
+            find("STRASSE", "die Straße", true, Normalization::None),
+            Some(4..11)
+        );
+        assert_eq!(
+            find("straße", "DIE STRASSE", true, Normalization::None),
+            Some(4..11)
+        );
+        // Part of an expansion cannot be told apart, so the whole original char is returned
+        assert_eq!(find("s", "ß", true, Normalization::None), Some(0..2));
+        assert_eq!(
+            find("STRASSE", "die Straße", false, Normalization::None),
+            None
+        );
+    }
+
+    #[test]
+    fn composed_and_decomposed_text_match_each_other() {
+        let composed = "caf\u{e9} ok";
+        let decomposed = "cafe\u{301} ok";
+        for normalization in [Normalization::Nfc, Normalization::Nfd] {
+            assert_eq!(
+                find("caf\u{e9}", decomposed, false, normalization),
+                Some(0..6)
+            );
+            assert_eq!(
+                find("cafe\u{301}", composed, false, normalization),
+                Some(0..5)
+            );
+            assert_eq!(
+                find("CAF\u{c9}", decomposed, true, normalization),
+                Some(0..6)
+            );
+        }
+        assert_eq!(
+            find("caf\u{e9}", decomposed, false, Normalization::None),
+            None
+        );
+        // The base char alone does not match a char carrying a mark
+        assert_eq!(find("cafe ", decomposed, false, Normalization::Nfc), None);
+    }
+
+    #[test]
+    fn hangul_jamo_compose_across_starters() {
+        let syllable = "x\u{ac00}y";
+        let jamo = "x\u{1100}\u{1161}y";
+        for normalization in [Normalization::Nfc, Normalization::Nfd] {
+            assert_eq!(find("\u{ac00}", jamo, false, normalization), Some(1..7));
+            assert_eq!(
+                find("\u{1100}\u{1161}", syllable, false, normalization),
+                Some(1..4)
+            );
+        }
+        // A trailing consonant joins the syllable too
+        assert_eq!(
+            find(
+                "\u{ac01}",
+                "\u{1100}\u{1161}\u{11a8}",
+                false,
+                Normalization::Nfc
+            ),
+            Some(0..9)
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::report::FileReport;
+
+/** A matching line, with the byte range of the first match within it. */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Match {
+    /** Position of the source in the list given to the strategy. */
+    pub source: usize,
+    pub name: String,
+    pub line_number: usize,
+    pub line: String,
+    pub span: Range<usize>,
+}
+
+/** Receives matches as a strategy finds them. Strategies may call it from several threads. */
+pub trait Output: Sync {
+    fn matched(&self, found: Match);
+
+    /** Called once a source has been fully searched. */
+    fn finished(&self, _report: &FileReport) {}
+}
+
+/** Prints every match as `name:line_number`. */
+pub struct LineOutput;
+
+impl Output for LineOutput {
+    fn matched(&self, found: Match) {
+        println!("{}:{}", found.name, found.line_number);
+    }
+}
+
+/** Groups matches by source: the name once, then its matching lines and a match count.
+Each block is written in one piece once its source is finished, so blocks from
+concurrent strategies never interleave. Sources without matches print nothing. */
+pub struct HeadingOutput {
+    pending: Mutex<HashMap<usize, Vec<Match>>>,
+}
+
+impl Default for HeadingOutput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HeadingOutput {
+    pub fn new() -> HeadingOutput {
+        HeadingOutput {
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn block(report: &FileReport, mut matches: Vec<Match>) -> String {
+        // Chunked strategies find a source's matches out of order
+        matches.sort_by_key(|found| found.line_number);
+        let mut block = format!("{}\n", report.name);
+        for found in &matches {
+            block.push_str(&format!("{}:{}\n", found.line_number, found.line));
+        }
+        let noun = if matches.len() == 1 {
+            "match"
+        } else {
+            "matches"
+        };
+        block.push_str(&format!("({} {})\n\n", matches.len(), noun));
+        block
+    }
+}
+
+impl Output for HeadingOutput {
+    fn matched(&self, found: Match) {
+        let mut pending = self.pending.lock().unwrap();
+        pending.entry(found.source).or_default().push(found);
+    }
+
+    fn finished(&self, report: &FileReport) {
+        let matches = self.pending.lock().unwrap().remove(&report.source);
+        let Some(matches) = matches else {
+            return;
+        };
+        let block = Self::block(report, matches);
+        let _ = io::stdout().lock().write_all(block.as_bytes());
+    }
+}
+
+/** Keeps matches in memory instead of printing them, optionally only the first few. */
+pub struct CollectOutput {
+    matches: Mutex<Vec<Match>>,
+    limit: Option<usize>,
+    total: AtomicUsize,
+}
+
+impl Default for CollectOutput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CollectOutput {
+    pub fn new() -> CollectOutput {
+        CollectOutput {
+            matches: Mutex::new(Vec::new()),
+            limit: None,
+            total: AtomicUsize::new(0),
+        }
+    }
+
+    /** Keeps only the first `limit` matches in source and line order, counting the rest,
+    so broad patterns do not hold every match in memory. */
+    pub fn with_limit(limit: usize) -> CollectOutput {
+        CollectOutput {
+            limit: Some(limit),
+            ..Self::new()
+        }
+    }
+
+    /** How many matches were found, including those not kept. */
+    pub fn total(&self) -> usize {
+        self.total.load(Ordering::Relaxed)
+    }
+
+    /** Returns the collected matches: in the order they were found, or with a limit,
+    the first ones in source and line order. */
+    pub fn into_matches(self) -> Vec<Match> {
+        let mut matches = self.matches.into_inner().unwrap();
+        if let Some(limit) = self.limit {
+            Self::keep_first(&mut matches, limit);
+        }
+        matches
+    }
+
+    fn keep_first(matches: &mut Vec<Match>, limit: usize) {
+        matches.sort_by_key(|found| (found.source, found.line_number));
+        matches.truncate(limit);
+    }
+}
+
+impl Output for CollectOutput {
+    fn matched(&self, found: Match) {
+        self.total.fetch_add(1, Ordering::Relaxed);
+        let mut matches = self.matches.lock().unwrap();
+        matches.push(found);
+        // Trimming only once twice the limit is reached keeps it cheap
+        if let Some(limit) = self.limit
+            && matches.len() >= limit.saturating_mul(2).max(1)
+        {
+            Self::keep_first(&mut matches, limit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limited_collection_keeps_the_first_matches_and_counts_all() {
+        let output = CollectOutput::with_limit(2);
+        for (source, line_number) in [(1, 5), (0, 9), (1, 1), (0, 3), (2, 1)] {
+            output.matched(Match {
+                source,
+                name: source.to_string(),
+                line_number,
+                line: String::new(),
+                span: 0..0,
+            });
+        }
+        assert_eq!(output.total(), 5);
+        let kept: Vec<_> = output
+            .into_matches()
+            .iter()
+            .map(|found| (found.source, found.line_number))
+            .collect();
+        assert_eq!(kept, vec![(0, 3), (0, 9)]);
+    }
+}
@@ -0,0 +1,209 @@
+use std::io::{self, BufRead, Read};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/** What a search found in a single source. */
+pub struct FileReport {
+    /** Position of the source in the list given to the strategy. */
+    pub source: usize,
+    pub name: String,
+    /** Lines read, including those skipped because they could not be decoded. */
+    pub lines: usize,
+    pub bytes: usize,
+    pub matches: usize,
+    pub elapsed: Duration,
+}
+
+/** A source that could not be opened, or that failed partway through being read. */
+pub struct SourceError {
+    /** Position of the source in the list given to the strategy. */
+    pub source: usize,
+    pub name: String,
+    pub error: io::Error,
+}
+
+/** Everything a strategy measured while running a search. */
+pub struct SearchReport {
+    /** The sources that were read, fully or up to a read error. */
+    pub files: Vec<FileReport>,
+    /** Open and read errors, in source order. Their sources are left out or cut short. */
+    pub errors: Vec<SourceError>,
+    pub elapsed: Duration,
+    pub threads: usize,
+    pub peak_threads: usize,
+}
+
+impl SearchReport {
+    /** Total number of matching lines across all files. */
+    pub fn matches(&self) -> usize {
+        self.files.iter().map(|file| file.matches).sum()
+    }
+
+    /** Total number of lines scanned across all files. */
+    pub fn lines(&self) -> usize {
+        self.files.iter().map(|file| file.lines).sum()
+    }
+
+    /** Total number of bytes read across all files. */
+    pub fn bytes(&self) -> usize {
+        self.files.iter().map(|file| file.bytes).sum()
+    }
+
+    /** Bytes read per second of wall time, in MB/s. */
+    pub fn throughput(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds == 0.0 {
+            return 0.0;
+        }
+        self.bytes() as f64 / 1_000_000.0 / seconds
+    }
+
+    pub fn as_string(&self) -> String {
+        let mut result = String::new();
+        for file in &self.files {
+            result.push_str(&format!(
+                "{}: {} lines, {} bytes, {} matches, {:?}\n",
+                file.name, file.lines, file.bytes, file.matches, file.elapsed
+            ));
+        }
+        result.push_str(&format!(
+            "Total: {} matches in {} files, {} lines, {} bytes, {:?} ({:.2} MB/s)\n",
+            self.matches(),
+            self.files.len(),
+            self.lines(),
+            self.bytes(),
+            self.elapsed,
+            self.throughput()
+        ));
+        result.push_str(&format!(
+            "Threads: {} used, {} peak",
+            self.threads, self.peak_threads
+        ));
+        result
+    }
+}
+
+/** Collects the errors met by the threads of a search. */
+pub(crate) struct ErrorLog {
+    errors: Mutex<Vec<SourceError>>,
+}
+
+impl ErrorLog {
+    pub(crate) fn new() -> ErrorLog {
+        ErrorLog {
+            errors: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn record(&self, source: usize, name: &str, error: io::Error) {
+        self.errors.lock().unwrap().push(SourceError {
+            source,
+            name: name.to_string(),
+            error,
+        });
+    }
+
+    /** The errors recorded, in source order. */
+    pub(crate) fn into_errors(self) -> Vec<SourceError> {
+        let mut errors = self.errors.into_inner().unwrap();
+        errors.sort_by_key(|error| error.source);
+        errors
+    }
+}
+
+/** Counts the threads a search spawns and the most that were alive at once.
+The calling thread counts as the first one. */
+pub(crate) struct ThreadTracker {
+    spawned: AtomicUsize,
+    alive: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl ThreadTracker {
+    pub(crate) fn new() -> ThreadTracker {
+        ThreadTracker {
+            spawned: AtomicUsize::new(1),
+            alive: AtomicUsize::new(1),
+            peak: AtomicUsize::new(1),
+        }
+    }
+
+    /** Registers the current thread as alive until the guard is dropped. */
+    pub(crate) fn enter(&self) -> ThreadGuard<'_> {
+        self.spawned.fetch_add(1, Ordering::Relaxed);
+        let alive = self.alive.fetch_add(1, Ordering::AcqRel) + 1;
+        self.peak.fetch_max(alive, Ordering::AcqRel);
+        ThreadGuard { tracker: self }
+    }
+
+    pub(crate) fn spawned(&self) -> usize {
+        self.spawned.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn peak(&self) -> usize {
+        self.peak.load(Ordering::Acquire)
+    }
+}
+
+pub(crate) struct ThreadGuard<'a> {
+    tracker: &'a ThreadTracker,
+}
+
+impl Drop for ThreadGuard<'_> {
+    fn drop(&mut self) {
+        self.tracker.alive.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/** The number of bytes read from a source, shared with the `CountingReader` that counts them. */
+#[derive(Clone, Default)]
+pub(crate) struct ByteCount(Arc<AtomicUsize>);
+
+impl ByteCount {
+    pub(crate) fn new() -> ByteCount {
+        ByteCount::default()
+    }
+
+    pub(crate) fn get(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn add(&self, bytes: usize) {
+        self.0.fetch_add(bytes, Ordering::Relaxed);
+    }
+}
+
+/** Wraps a reader and counts the bytes consumed from it into a `ByteCount`. */
+pub(crate) struct CountingReader<R> {
+    inner: R,
+    count: ByteCount,
+}
+
+impl<R> CountingReader<R> {
+    pub(crate) fn new(inner: R, count: &ByteCount) -> CountingReader<R> {
+        CountingReader {
+            inner,
+            count: count.clone(),
+        }
+    }
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.count.add(read);
+        Ok(read)
+    }
+}
+
+impl<R: BufRead> BufRead for CountingReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.count.add(amount);
+        self.inner.consume(amount);
+    }
+}
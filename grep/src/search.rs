@@ -1,113 +1,557 @@
-use std::fs::File;
-use std::io::{self, BufRead};
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::io::{self, BufRead, ErrorKind};
+use std::sync::{Arc, Mutex, mpsc};
 use std::thread;
+use std::time::{Duration, Instant};
 
 use crate::SearchStrategy;
-use crate::bruteforce::bruteforce;
+use crate::matcher::Matcher;
+use crate::output::{Match, Output};
+use crate::report::{ByteCount, ErrorLog, FileReport, SearchReport, ThreadTracker};
+use crate::source::Source;
+
+/** Builds the strategy selected by a mode name: `seq`, `conc`, `c-chunk`, `pipe` or `auto`. */
+pub fn strategy_from_mode(mode: &str) -> Option<Box<dyn SearchStrategy>> {
+    let strategy: Box<dyn SearchStrategy> = match mode {
+        "seq" => Box::new(SequentialSearch),
+        "conc" => Box::new(ConcurrentSearch),
+        "c-chunk" => Box::new(ChunkedConcurrentSearch { chunk_size: 1024 }), // 1 KB por chunk
+        "pipe" => Box::new(PipelinedSearch {
+            matchers: thread::available_parallelism().map_or(4, |n| n.get()),
+            batch_size: 256,
+            queue_capacity: 16,
+        }),
+        "auto" => Box::new(AutoSearch::new()),
+        _ => return None,
+    };
+    Some(strategy)
+}
+
+/** Reads the lines of `reader`. Lines that are not valid UTF-8 come out as None, so they
+can be skipped while still taking up a line number. Any other read error ends the input
+and is left in `failure`. */
+fn decoded_lines<'a>(
+    reader: impl BufRead + 'a,
+    failure: &'a mut Option<io::Error>,
+) -> impl Iterator<Item = Option<String>> + 'a {
+    reader.lines().map_while(move |line| match line {
+        Ok(line) => Some(Some(line)),
+        Err(e) if e.kind() == ErrorKind::InvalidData => Some(None),
+        Err(e) => {
+            *failure = Some(e);
+            None
+        }
+    })
+}
+
+/** Searches one source line by line, reporting each match as it is found.
+Returns None when the source cannot be opened; that and read errors go to `errors`. */
+fn scan_source(
+    index: usize,
+    source: Source,
+    matcher: &Matcher,
+    output: &dyn Output,
+    errors: &ErrorLog,
+) -> Option<FileReport> {
+    let start = Instant::now();
+    let name = source.name().to_string();
+    let bytes = ByteCount::new();
+    let mut reader = match source.open_counted(&bytes) {
+        Ok(reader) => reader,
+        Err(error) => {
+            errors.record(index, &name, error);
+            return None;
+        }
+    };
+    let mut lines = 0;
+    let mut matches = 0;
+    let mut failure = None;
+    for (line_number, line) in decoded_lines(&mut reader, &mut failure).enumerate() {
+        // Undecodable lines are not searched, but they were still scanned
+        lines += 1;
+        let Some(line) = line else {
+            continue;
+        };
+        if let Some(span) = matcher.find(&line) {
+            output.matched(Match {
+                source: index,
+                name: name.clone(),
+                line_number: line_number + 1,
+                line,
+                span,
+            });
+            matches += 1;
+        }
+    }
+    let report = FileReport {
+        source: index,
+        name,
+        lines,
+        bytes: bytes.get(),
+        matches,
+        elapsed: start.elapsed(),
+    };
+    if let Some(error) = failure {
+        errors.record(index, &report.name, error);
+    }
+    output.finished(&report);
+    Some(report)
+}
 
 pub struct SequentialSearch;
 
 impl SearchStrategy for SequentialSearch {
-    fn search(&self, file_paths: &[String], pattern: &str) -> usize {
-        let mut count = 0;
-        for file_path in file_paths {
-            let file = File::open(file_path).expect("Error opening file");
-            let reader = io::BufReader::new(file);
-            for (line_number, line) in reader.lines().enumerate() {
-                if let Ok(line) = line {
-                    if bruteforce(&line, pattern) {
-                        println!("{}:{}", file_path, line_number + 1);
-                        count += 1;
-                    }
-                }
-            }
+    fn search_report(
+        &self,
+        sources: Vec<Source>,
+        matcher: &Matcher,
+        output: &dyn Output,
+    ) -> SearchReport {
+        let start = Instant::now();
+        let errors = ErrorLog::new();
+        let files = sources
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, source)| scan_source(index, source, matcher, output, &errors))
+            .collect();
+        SearchReport {
+            files,
+            errors: errors.into_errors(),
+            elapsed: start.elapsed(),
+            threads: 1,
+            peak_threads: 1,
         }
-        count
     }
 }
 
 pub struct ConcurrentSearch;
 
 impl SearchStrategy for ConcurrentSearch {
+    fn search_report(
+        &self,
+        sources: Vec<Source>,
+        matcher: &Matcher,
+        output: &dyn Output,
+    ) -> SearchReport {
+        let start = Instant::now();
+        let tracker = ThreadTracker::new();
+        let errors = ErrorLog::new();
+
+        let files = thread::scope(|scope| {
+            let mut handles = vec![];
+
+            for (index, source) in sources.into_iter().enumerate() {
+                let tracker = &tracker;
+                let errors = &errors;
+                let handle = scope.spawn(move || {
+                    let _guard = tracker.enter();
+                    scan_source(index, source, matcher, output, errors)
+                });
+
+                handles.push(handle);
+            }
+
+            // Collect the results from each thread
+            handles
+                .into_iter()
+                .filter_map(|handle| handle.join().ok().flatten())
+                .collect()
+        });
+
+        SearchReport {
+            files,
+            errors: errors.into_errors(),
+            elapsed: start.elapsed(),
+            threads: tracker.spawned(),
+            peak_threads: tracker.peak(),
+        }
+    }
+}
+
+pub struct ChunkedConcurrentSearch {
+    pub chunk_size: usize,
+}
+
+impl SearchStrategy for ChunkedConcurrentSearch {
+    fn search_report(
+        &self,
+        sources: Vec<Source>,
+        matcher: &Matcher,
+        output: &dyn Output,
+    ) -> SearchReport {
+        let start = Instant::now();
+        let tracker = ThreadTracker::new();
+        let chunk_size = self.chunk_size.max(1);
+        let errors = ErrorLog::new();
+        let mut files = vec![];
+
+        for (index, source) in sources.into_iter().enumerate() {
+            let file_start = Instant::now();
+            let name = source.name().to_string();
+            let bytes = ByteCount::new();
+            let mut reader = match source.open_counted(&bytes) {
+                Ok(reader) => reader,
+                Err(error) => {
+                    errors.record(index, &name, error);
+                    continue;
+                }
+            };
+            let mut failure = None;
+            let lines: Vec<Option<String>> = decoded_lines(&mut reader, &mut failure).collect();
+            if let Some(error) = failure {
+                errors.record(index, &name, error);
+            }
+            let mut matches = 0;
+
+            thread::scope(|scope| {
+                let mut handles = vec![];
+
+                for (chunk_index, chunk) in lines.chunks(chunk_size).enumerate() {
+                    let name = &name;
+                    let tracker = &tracker;
 
-    fn search(&self, file_paths: &[String], pattern: &str) -> usize {
-        let pattern = Arc::new(pattern.to_string());
-        let mut handles = vec![];
-
-        for file_path in file_paths {
-            let file_path = file_path.clone();
-            let pattern = Arc::clone(&pattern);
-
-            let handle = thread::spawn(move || {
-                let mut local_count = 0;
-                if let Ok(file) = File::open(&file_path) {
-                    let reader = io::BufReader::new(file);
-                    for (line_number, line) in reader.lines().enumerate() {
-                        if let Ok(line) = line {
-                            if bruteforce(&line, &pattern) {
+                    let handle = scope.spawn(move || {
+                        let _guard = tracker.enter();
+                        let mut local_count = 0;
+                        for (i, line) in chunk.iter().enumerate() {
+                            let Some(line) = line else {
+                                continue;
+                            };
+                            if let Some(span) = matcher.find(line) {
+                                let global_line_number = chunk_index * chunk_size + i + 1;
+                                output.matched(Match {
+                                    source: index,
+                                    name: name.clone(),
+                                    line_number: global_line_number,
+                                    line: line.clone(),
+                                    span,
+                                });
                                 local_count += 1;
-                                println!("{}:{}", file_path, line_number + 1);
                             }
                         }
-                    }
+                        local_count
+                    });
+
+                    handles.push(handle);
+                }
+                for handle in handles {
+                    matches += handle.join().unwrap_or(0);
                 }
-                local_count
             });
 
-            handles.push(handle);
+            let report = FileReport {
+                source: index,
+                name,
+                lines: lines.len(),
+                bytes: bytes.get(),
+                matches,
+                elapsed: file_start.elapsed(),
+            };
+            output.finished(&report);
+            files.push(report);
         }
 
-        // Sum the results from each thread
-        handles
-            .into_iter()
-            .map(|handle| handle.join().unwrap_or(0))
-            .sum()
+        SearchReport {
+            files,
+            errors: errors.into_errors(),
+            elapsed: start.elapsed(),
+            threads: tracker.spawned(),
+            peak_threads: tracker.peak(),
+        }
     }
+}
 
+/** A batch of consecutive lines read from a single source. */
+struct Batch {
+    sequence: usize,
+    file_index: usize,
+    name: Arc<String>,
+    started: Instant,
+    first_line: usize,
+    bytes: usize,
+    /** None for lines that are not valid UTF-8. */
+    lines: Vec<Option<String>>,
 }
 
-pub struct ChunkedConcurrentSearch {
-    pub chunk_size: usize,
+/** The lines that matched within a batch, tagged with the batch's sequence. */
+struct BatchResult {
+    sequence: usize,
+    file_index: usize,
+    name: Arc<String>,
+    started: Instant,
+    lines: usize,
+    bytes: usize,
+    found: Vec<Match>,
 }
 
-impl SearchStrategy for ChunkedConcurrentSearch {
-    fn search(&self, file_paths: &[String], pattern: &str) -> usize {
-        let pattern = Arc::new(pattern.to_string());
-        let mut total_count = 0;
-
-        for file_path in file_paths {
-            let file_path = file_path.clone();
-            let pattern = Arc::clone(&pattern);
-            let file = File::open(&file_path).expect("Error opening the file");
-            let reader = io::BufReader::new(file);
-            let lines: Vec<String> = reader.lines().filter_map(Result::ok).collect();
-            let mut handles = vec![];
+/** Staged pipeline: one reader thread fills a bounded queue with line batches,
+`matchers` threads consume them, and a printer stage emits results in input order. */
+pub struct PipelinedSearch {
+    pub matchers: usize,
+    pub batch_size: usize,
+    pub queue_capacity: usize,
+}
+
+impl SearchStrategy for PipelinedSearch {
+    fn search_report(
+        &self,
+        sources: Vec<Source>,
+        matcher: &Matcher,
+        output: &dyn Output,
+    ) -> SearchReport {
+        let start = Instant::now();
+        let tracker = ThreadTracker::new();
+        let batch_size = self.batch_size.max(1);
+        let (batch_tx, batch_rx) = mpsc::sync_channel::<Batch>(self.queue_capacity);
+        let (result_tx, result_rx) = mpsc::channel::<BatchResult>();
+        let batch_rx = Arc::new(Mutex::new(batch_rx));
+        let errors = ErrorLog::new();
+
+        let files = thread::scope(|scope| {
+            // Reader stage: all I/O happens here
+            let tracker = &tracker;
+            let errors = &errors;
+            scope.spawn(move || {
+                let _guard = tracker.enter();
+                let mut sequence = 0;
+                for (file_index, source) in sources.into_iter().enumerate() {
+                    let started = Instant::now();
+                    let name = Arc::new(source.name().to_string());
+                    let bytes = ByteCount::new();
+                    let mut reader = match source.open_counted(&bytes) {
+                        Ok(reader) => reader,
+                        Err(error) => {
+                            errors.record(file_index, &name, error);
+                            continue;
+                        }
+                    };
+                    let mut first_line = 1;
+                    // Starts at zero so the byte order mark, read on opening, is counted
+                    let mut counted = 0;
+                    loop {
+                        let mut failure = None;
+                        let chunk: Vec<Option<String>> = decoded_lines(&mut reader, &mut failure)
+                            .take(batch_size)
+                            .collect();
+                        // Every source sends at least one batch so it shows up in the report
+                        let last = chunk.len() < batch_size || failure.is_some();
+                        if let Some(error) = failure {
+                            errors.record(file_index, &name, error);
+                        }
+                        if chunk.is_empty() && first_line > 1 {
+                            break;
+                        }
+                        let next_line = first_line + chunk.len();
+                        let total = bytes.get();
+                        let batch = Batch {
+                            sequence,
+                            file_index,
+                            name: Arc::clone(&name),
+                            started,
+                            first_line,
+                            bytes: total - counted,
+                            lines: chunk,
+                        };
+                        counted = total;
+                        if batch_tx.send(batch).is_err() {
+                            return;
+                        }
+                        sequence += 1;
+                        first_line = next_line;
+                        if last {
+                            break;
+                        }
+                    }
+                }
+            });
 
-            for (chunk_index, chunk) in lines.chunks(self.chunk_size).enumerate() {
-                let chunk = chunk.to_owned(); // clone chunk for thread
-                let pattern = Arc::clone(&pattern);
-                let file_path = file_path.clone();
-
-                let handle = thread::spawn(move || {
-                    let mut local_count = 0;
-                    for (i, line) in chunk.iter().enumerate() {
-                        if bruteforce(line, &pattern) {
-                            let global_line_number = chunk_index * chunk.len() + i + 1;
-                            println!("{}:{}", file_path, global_line_number);
-                            local_count += 1;
+            // Matcher stage: CPU work only
+            for _ in 0..self.matchers.max(1) {
+                let batch_rx = Arc::clone(&batch_rx);
+                let result_tx = result_tx.clone();
+                scope.spawn(move || {
+                    let _guard = tracker.enter();
+                    loop {
+                        let batch = batch_rx.lock().unwrap().recv();
+                        let Ok(batch) = batch else {
+                            break;
+                        };
+                        let lines = batch.lines.len();
+                        let found = batch
+                            .lines
+                            .into_iter()
+                            .enumerate()
+                            .filter_map(|(i, line)| {
+                                let line = line?;
+                                let span = matcher.find(&line)?;
+                                Some(Match {
+                                    source: batch.file_index,
+                                    name: batch.name.to_string(),
+                                    line_number: batch.first_line + i,
+                                    line,
+                                    span,
+                                })
+                            })
+                            .collect();
+                        let result = BatchResult {
+                            sequence: batch.sequence,
+                            file_index: batch.file_index,
+                            name: batch.name,
+                            started: batch.started,
+                            lines,
+                            bytes: batch.bytes,
+                            found,
+                        };
+                        if result_tx.send(result).is_err() {
+                            break;
                         }
                     }
-                    local_count
                 });
+            }
+            drop(result_tx);
 
-                handles.push(handle);
+            // Printer stage: buffers out-of-order results until their turn comes
+            let mut pending = HashMap::new();
+            let mut next_sequence = 0;
+            let mut current_file = None;
+            let mut files: Vec<FileReport> = vec![];
+            for result in result_rx {
+                pending.insert(result.sequence, result);
+                while let Some(result) = pending.remove(&next_sequence) {
+                    if current_file != Some(result.file_index) {
+                        if let Some(file) = files.last() {
+                            output.finished(file);
+                        }
+                        current_file = Some(result.file_index);
+                        files.push(FileReport {
+                            source: result.file_index,
+                            name: result.name.to_string(),
+                            lines: 0,
+                            bytes: 0,
+                            matches: 0,
+                            elapsed: Duration::ZERO,
+                        });
+                    }
+                    let file = files.last_mut().unwrap();
+                    file.lines += result.lines;
+                    file.bytes += result.bytes;
+                    file.matches += result.found.len();
+                    file.elapsed = result.started.elapsed();
+                    for found in result.found {
+                        output.matched(found);
+                    }
+                    next_sequence += 1;
+                }
             }
-            for handle in handles {
-                total_count += handle.join().unwrap_or(0);
+            if let Some(file) = files.last() {
+                output.finished(file);
             }
+            files
+        });
+
+        SearchReport {
+            files,
+            errors: errors.into_errors(),
+            elapsed: start.elapsed(),
+            threads: tracker.spawned(),
+            peak_threads: tracker.peak(),
         }
-        total_count
     }
 }
 
+/** Picks a strategy by looking at the input before searching it: sequential search for
+small inputs, one thread per file for many files and chunked search for a few huge files. */
+pub struct AutoSearch {
+    pub cores: usize,
+    /** Inputs smaller than this many bytes in total are searched sequentially. */
+    pub small_input: u64,
+    /** Files larger than this many bytes are split into chunks. */
+    pub huge_file: u64,
+    /** Prints the reasoning behind the choice to stderr. */
+    pub debug: bool,
+}
+
+/** Rough average line length, used to turn a file size into a chunk size in lines. */
+const ESTIMATED_LINE_BYTES: u64 = 80;
+
+impl Default for AutoSearch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AutoSearch {
+    pub fn new() -> AutoSearch {
+        AutoSearch {
+            cores: thread::available_parallelism().map_or(1, |n| n.get()),
+            small_input: 1024 * 1024,
+            huge_file: 64 * 1024 * 1024,
+            debug: false,
+        }
+    }
+
+    /** Chooses a strategy for `sources`, along with a human readable reason. */
+    pub fn choose(&self, sources: &[Source]) -> (Box<dyn SearchStrategy>, String) {
+        let sizes: Vec<Option<u64>> = sources.iter().map(Source::size_hint).collect();
+        let known: Vec<u64> = sizes.iter().flatten().copied().collect();
+        let total: u64 = known.iter().sum();
+        let largest = known.iter().copied().max().unwrap_or(0);
+        let unknown = sizes.len() - known.len();
+        let summary = format!(
+            "{} files ({} of unknown size), {} bytes total, largest {} bytes, {} cores",
+            sources.len(),
+            unknown,
+            total,
+            largest,
+            self.cores
+        );
+
+        if self.cores <= 1 {
+            let reason = format!("{} -> seq: only one core available", summary);
+            return (Box::new(SequentialSearch), reason);
+        }
+        if unknown == 0 && total < self.small_input {
+            let reason = format!(
+                "{} -> seq: input is under {} bytes, threads would cost more than they save",
+                summary, self.small_input
+            );
+            return (Box::new(SequentialSearch), reason);
+        }
+        if sources.len() < self.cores && largest >= self.huge_file {
+            let lines = largest / ESTIMATED_LINE_BYTES;
+            let chunk_size = (lines / self.cores as u64).max(1024) as usize;
+            let reason = format!(
+                "{} -> c-chunk with {} lines per chunk: fewer files than cores and at least one over {} bytes",
+                summary, chunk_size, self.huge_file
+            );
+            return (Box::new(ChunkedConcurrentSearch { chunk_size }), reason);
+        }
+        if sources.len() == 1 {
+            let reason = format!(
+                "{} -> seq: a single file under {} bytes is not worth splitting",
+                summary, self.huge_file
+            );
+            return (Box::new(SequentialSearch), reason);
+        }
+        let reason = format!(
+            "{} -> conc: input is spread over several files, one thread per file",
+            summary
+        );
+        (Box::new(ConcurrentSearch), reason)
+    }
+}
+
+impl SearchStrategy for AutoSearch {
+    fn search_report(
+        &self,
+        sources: Vec<Source>,
+        matcher: &Matcher,
+        output: &dyn Output,
+    ) -> SearchReport {
+        let (strategy, reason) = self.choose(&sources);
+        if self.debug {
+            eprintln!("auto: {}", reason);
+        }
+        strategy.search_report(sources, matcher, output)
+    }
+}
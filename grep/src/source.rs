@@ -0,0 +1,100 @@
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read};
+
+use crate::encoding::{DecodingReader, Encoding};
+use crate::report::{ByteCount, CountingReader};
+
+enum SourceKind<'a> {
+    Path(String),
+    Bytes(&'a [u8]),
+    Reader(Box<dyn Read + Send + 'a>),
+}
+
+/** Something a search strategy can read lines from. */
+pub struct Source<'a> {
+    name: String,
+    kind: SourceKind<'a>,
+    encoding: Option<Encoding>,
+}
+
+impl<'a> Source<'a> {
+    /** A file on disk, opened when the search reaches it. */
+    pub fn path(path: impl Into<String>) -> Source<'a> {
+        let path = path.into();
+        Source {
+            name: path.clone(),
+            kind: SourceKind::Path(path),
+            encoding: None,
+        }
+    }
+
+    /** An in-memory buffer, reported under `name`. */
+    pub fn bytes(name: impl Into<String>, bytes: &'a [u8]) -> Source<'a> {
+        Source {
+            name: name.into(),
+            kind: SourceKind::Bytes(bytes),
+            encoding: None,
+        }
+    }
+
+    /** Any stream, such as a socket or a request body, reported under `name`. */
+    pub fn reader(name: impl Into<String>, reader: impl Read + Send + 'a) -> Source<'a> {
+        Source {
+            name: name.into(),
+            kind: SourceKind::Reader(Box::new(reader)),
+            encoding: None,
+        }
+    }
+
+    /** Reads the source as `encoding` instead of guessing it from a byte order mark. */
+    pub fn with_encoding(mut self, encoding: Encoding) -> Source<'a> {
+        self.encoding = Some(encoding);
+        self
+    }
+
+    /** The name printed next to each match. */
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /** The size of the source in bytes, when it can be known without reading it. */
+    pub fn size_hint(&self) -> Option<u64> {
+        match &self.kind {
+            SourceKind::Path(path) => fs::metadata(path).ok().map(|metadata| metadata.len()),
+            SourceKind::Bytes(bytes) => Some(bytes.len() as u64),
+            SourceKind::Reader(_) => None,
+        }
+    }
+
+    /** Consumes the source, returning a buffered reader over its contents as UTF-8.
+    Without an explicit encoding, UTF-16 sources are recognized by their byte order mark. */
+    pub fn open(self) -> io::Result<Box<dyn BufRead + Send + 'a>> {
+        self.open_counted(&ByteCount::new())
+    }
+
+    /** Like `open`, counting into `count` the bytes read from the source itself,
+    byte order mark included, rather than the UTF-8 they are decoded to. */
+    pub(crate) fn open_counted(
+        self,
+        count: &ByteCount,
+    ) -> io::Result<Box<dyn BufRead + Send + 'a>> {
+        let raw: Box<dyn BufRead + Send + 'a> = match self.kind {
+            SourceKind::Path(path) => Box::new(BufReader::new(File::open(path)?)),
+            SourceKind::Bytes(bytes) => Box::new(bytes),
+            SourceKind::Reader(reader) => Box::new(BufReader::new(reader)),
+        };
+        let mut reader: Box<dyn BufRead + Send + 'a> = Box::new(CountingReader::new(raw, count));
+        let encoding = match self.encoding {
+            Some(encoding) => encoding,
+            None => Encoding::detect(reader.fill_buf()?),
+        };
+        let bom = encoding.bom();
+        if !bom.is_empty() && reader.fill_buf()?.starts_with(bom) {
+            reader.consume(bom.len());
+        }
+        match encoding {
+            Encoding::Utf8 => Ok(reader),
+            encoding => Ok(Box::new(DecodingReader::new(reader, encoding))),
+        }
+    }
+}
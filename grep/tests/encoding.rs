@@ -0,0 +1,102 @@
+use std::io::{BufRead, BufReader, Read};
+
+use grep::SearchStrategy;
+use grep::encoding::{DecodingReader, Encoding};
+use grep::matcher::{MatchOptions, Matcher};
+use grep::output::CollectOutput;
+use grep::search::{ChunkedConcurrentSearch, PipelinedSearch, SequentialSearch};
+use grep::source::Source;
+
+fn utf16(text: &str, encoding: Encoding) -> Vec<u8> {
+    let mut bytes = encoding.bom().to_vec();
+    for unit in text.encode_utf16() {
+        match encoding {
+            Encoding::Utf16Le => bytes.extend(unit.to_le_bytes()),
+            _ => bytes.extend(unit.to_be_bytes()),
+        }
+    }
+    bytes
+}
+
+fn lines(source: Source) -> Vec<String> {
+    source.open().unwrap().lines().map(Result::unwrap).collect()
+}
+
+#[test]
+fn utf16_sources_are_detected_by_their_bom() {
+    let text = "first\r\nsecond ünïcode 😀\nthird";
+    for encoding in [Encoding::Utf16Le, Encoding::Utf16Be] {
+        let bytes = utf16(text, encoding);
+        assert_eq!(
+            lines(Source::bytes("log", &bytes)),
+            vec!["first", "second ünïcode 😀", "third"]
+        );
+    }
+}
+
+#[test]
+fn utf8_bom_is_skipped() {
+    let bytes = b"\xEF\xBB\xBFexception here\n";
+    assert_eq!(lines(Source::bytes("log", bytes)), vec!["exception here"]);
+}
+
+#[test]
+fn latin1_must_be_requested_explicitly() {
+    let bytes = b"caf\xE9\nna\xEFve\n";
+    let source = Source::bytes("legacy", bytes).with_encoding(Encoding::Latin1);
+    assert_eq!(lines(source), vec!["café", "naïve"]);
+}
+
+#[test]
+fn surrogate_pairs_split_across_reads_are_decoded() {
+    // A one byte buffer hands every UTF-16 unit to the decoder in pieces
+    let bytes = utf16("a😀b", Encoding::Utf16Le);
+    let reader = BufReader::with_capacity(1, &bytes[2..]);
+    let mut text = String::new();
+    DecodingReader::new(reader, Encoding::Utf16Le)
+        .read_to_string(&mut text)
+        .unwrap();
+    assert_eq!(text, "a😀b");
+}
+
+#[test]
+fn utf16_matches_report_correct_line_numbers_and_text() {
+    let bytes = utf16("ok\nan Exception\nok\nException again\n", Encoding::Utf16Le);
+    let matcher = Matcher::new("Exception", MatchOptions::default());
+    let output = CollectOutput::new();
+    let report =
+        SequentialSearch.search_report(vec![Source::bytes("win.log", &bytes)], &matcher, &output);
+    let matches = output.into_matches();
+    assert_eq!(report.matches(), 2);
+    assert_eq!(matches[0].line_number, 2);
+    assert_eq!(matches[0].line, "an Exception");
+    assert_eq!(matches[1].line_number, 4);
+}
+
+#[test]
+fn bytes_read_are_counted_before_decoding() {
+    let utf16 = utf16("exception one\nexception two\n", Encoding::Utf16Le);
+    let latin1 = b"caf\xE9 exception\n".to_vec();
+    let sources = || {
+        vec![
+            Source::bytes("utf16", &utf16),
+            Source::bytes("latin1", &latin1).with_encoding(Encoding::Latin1),
+        ]
+    };
+    let strategies: Vec<Box<dyn SearchStrategy>> = vec![
+        Box::new(SequentialSearch),
+        Box::new(ChunkedConcurrentSearch { chunk_size: 1 }),
+        Box::new(PipelinedSearch {
+            matchers: 2,
+            batch_size: 1,
+            queue_capacity: 2,
+        }),
+    ];
+    let matcher = Matcher::new("exception", MatchOptions::default());
+    for strategy in strategies {
+        let report = strategy.search_report(sources(), &matcher, &CollectOutput::new());
+        let bytes: Vec<usize> = report.files.iter().map(|file| file.bytes).collect();
+        // The BOM counts too, and é is one byte in Latin-1 but two once decoded
+        assert_eq!(bytes, vec![utf16.len(), latin1.len()]);
+    }
+}
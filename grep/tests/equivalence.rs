@@ -0,0 +1,292 @@
+use grep::SearchStrategy;
+use grep::matcher::{MatchOptions, Matcher};
+use grep::output::{CollectOutput, Match};
+use grep::search::{
+    AutoSearch, ChunkedConcurrentSearch, ConcurrentSearch, PipelinedSearch, SequentialSearch,
+};
+use grep::source::Source;
+use proptest::prelude::*;
+
+/** Lines drawn from a small alphabet so random patterns actually match, with some
+multi-byte chars mixed in and empty lines allowed. */
+fn line() -> impl Strategy<Value = String> {
+    prop::collection::vec(
+        prop::sample::select(vec!['a', 'b', 'c', ' ', 'é', 'ß', 'ü', '字', '😀']),
+        0..12,
+    )
+    .prop_map(|chars| chars.into_iter().collect())
+}
+
+/** A line of a generated file. Undecodable lines hold a byte that is never valid UTF-8,
+so every strategy must skip them while still counting them in line numbers. */
+#[derive(Clone, Debug)]
+struct Line {
+    text: String,
+    undecodable: bool,
+}
+
+fn file() -> impl Strategy<Value = Vec<Line>> {
+    let line = (line(), prop::bool::weighted(0.1))
+        .prop_map(|(text, undecodable)| Line { text, undecodable });
+    prop::collection::vec(line, 0..40)
+}
+
+fn corpus() -> impl Strategy<Value = Vec<Vec<Line>>> {
+    prop::collection::vec(file(), 1..5)
+}
+
+/** Either a random short pattern, which may be longer than many lines, or a slice
+of some line in the corpus, which is guaranteed to match. */
+fn pattern_for(corpus: &[Vec<Line>]) -> impl Strategy<Value = String> + use<> {
+    let lines: Vec<String> = corpus
+        .iter()
+        .flatten()
+        .map(|line| line.text.clone())
+        .collect();
+    let random = prop::collection::vec(prop::sample::select(vec!['a', 'b', 'é', 'ß', '字']), 1..16)
+        .prop_map(|chars| chars.into_iter().collect::<String>())
+        .boxed();
+    if lines.is_empty() {
+        return random;
+    }
+    let slice = (
+        prop::sample::select(lines),
+        any::<prop::sample::Index>(),
+        1..6usize,
+    )
+        .prop_map(|(line, start, len)| {
+            let chars: Vec<char> = line.chars().collect();
+            if chars.is_empty() {
+                return "a".to_string();
+            }
+            let start = start.index(chars.len());
+            chars[start..(start + len).min(chars.len())]
+                .iter()
+                .collect()
+        })
+        .boxed();
+    prop_oneof![random, slice].boxed()
+}
+
+/** The bytes of each file, with lines ended by `\n` or, with `crlf`, by `\r\n`. */
+fn contents(corpus: &[Vec<Line>], trailing_newline: bool, crlf: bool) -> Vec<Vec<u8>> {
+    let ending: &[u8] = if crlf { b"\r\n" } else { b"\n" };
+    corpus
+        .iter()
+        .map(|lines| {
+            let lines: Vec<Vec<u8>> = lines
+                .iter()
+                .map(|line| {
+                    let mut bytes = line.text.clone().into_bytes();
+                    if line.undecodable {
+                        bytes.insert(bytes.len() / 2, 0x80);
+                    }
+                    bytes
+                })
+                .collect();
+            let mut bytes = lines.join(ending);
+            if trailing_newline && !lines.is_empty() {
+                bytes.extend_from_slice(ending);
+            }
+            bytes
+        })
+        .collect()
+}
+
+/** Every strategy, configured to split the input as much as the sizes allow. */
+fn strategies(chunk_size: usize, matchers: usize) -> Vec<(&'static str, Box<dyn SearchStrategy>)> {
+    vec![
+        ("seq", Box::new(SequentialSearch)),
+        ("conc", Box::new(ConcurrentSearch)),
+        ("c-chunk", Box::new(ChunkedConcurrentSearch { chunk_size })),
+        (
+            "pipe",
+            Box::new(PipelinedSearch {
+                matchers,
+                batch_size: chunk_size,
+                queue_capacity: 2,
+            }),
+        ),
+        ("auto", Box::new(AutoSearch::new())),
+        // Thresholds of zero make auto pick its concurrent strategies even for tiny inputs
+        (
+            "auto-split",
+            Box::new(AutoSearch {
+                cores: 8,
+                small_input: 0,
+                huge_file: 0,
+                debug: false,
+            }),
+        ),
+    ]
+}
+
+struct Run {
+    matches: Vec<Match>,
+    count: usize,
+    lines: usize,
+}
+
+/** Runs `strategy` over the files and returns its matches sorted, its match count and
+the number of lines it scanned. */
+fn run(strategy: &dyn SearchStrategy, files: &[Vec<u8>], pattern: &str) -> Run {
+    let sources = files
+        .iter()
+        .enumerate()
+        .map(|(i, bytes)| Source::bytes(format!("file{}", i), bytes))
+        .collect();
+    let matcher = Matcher::new(pattern, MatchOptions::default());
+    let output = CollectOutput::new();
+    let report = strategy.search_report(sources, &matcher, &output);
+    let mut matches = output.into_matches();
+    matches.sort_by(|a, b| (&a.name, a.line_number).cmp(&(&b.name, b.line_number)));
+    Run {
+        matches,
+        count: report.matches(),
+        lines: report.lines(),
+    }
+}
+
+/** The matches a correct search must find, computed with `str::find`. */
+fn expected(corpus: &[Vec<Line>], pattern: &str) -> Vec<Match> {
+    let mut matches = vec![];
+    for (i, lines) in corpus.iter().enumerate() {
+        for (line_number, line) in lines.iter().enumerate() {
+            if line.undecodable {
+                continue;
+            }
+            if let Some(start) = line.text.find(pattern) {
+                matches.push(Match {
+                    source: i,
+                    name: format!("file{}", i),
+                    line_number: line_number + 1,
+                    line: line.text.clone(),
+                    span: start..start + pattern.len(),
+                });
+            }
+        }
+    }
+    matches
+}
+
+fn corpus_and_pattern() -> impl Strategy<Value = (Vec<Vec<Line>>, String)> {
+    corpus().prop_flat_map(|corpus| {
+        let pattern = pattern_for(&corpus);
+        (Just(corpus), pattern)
+    })
+}
+
+proptest! {
+    #[test]
+    fn strategies_find_the_same_matches(
+        (corpus, pattern) in corpus_and_pattern(),
+        chunk_size in 1..8usize,
+        matchers in 1..4usize,
+        trailing_newline in any::<bool>(),
+        crlf in any::<bool>(),
+    ) {
+        let files = contents(&corpus, trailing_newline, crlf);
+        let expected = expected(&corpus, &pattern);
+        // Without a trailing newline, a final empty line leaves no trace in the file
+        let lines: usize = corpus
+            .iter()
+            .map(|lines| match lines.last() {
+                Some(last) if !trailing_newline && last.text.is_empty() && !last.undecodable => {
+                    lines.len() - 1
+                }
+                _ => lines.len(),
+            })
+            .sum();
+
+        for (mode, strategy) in strategies(chunk_size, matchers) {
+            let run = run(strategy.as_ref(), &files, &pattern);
+            prop_assert_eq!(&run.matches, &expected, "{} returned different matches", mode);
+            prop_assert_eq!(run.count, expected.len(), "{} returned a different count", mode);
+            prop_assert_eq!(run.lines, lines, "{} scanned a different number of lines", mode);
+        }
+    }
+}
+
+#[test]
+fn chunked_line_numbers_cross_chunk_boundaries() {
+    // 7 lines in chunks of 3: the last chunk is shorter than the others
+    let text = b"x\nx\nx\nx\nx\nx\nneedle\n";
+    let strategy = ChunkedConcurrentSearch { chunk_size: 3 };
+    let run = run(&strategy, &[text.to_vec()], "needle");
+    assert_eq!(run.count, 1);
+    assert_eq!(run.matches[0].line_number, 7);
+}
+
+#[test]
+fn pattern_longer_than_every_line_matches_nothing() {
+    let text = b"ab\nabc\n\n";
+    for (mode, strategy) in strategies(1, 2) {
+        let run = run(strategy.as_ref(), &[text.to_vec()], "abcd");
+        assert!(run.matches.is_empty(), "{} found a match", mode);
+        assert_eq!(run.count, 0, "{} counted a match", mode);
+    }
+}
+
+#[test]
+fn undecodable_lines_are_skipped_by_every_strategy() {
+    let text = b"error one\n\xff\xfe error\r\nerror three\nerror four\n";
+    for (mode, strategy) in strategies(1, 2) {
+        let run = run(strategy.as_ref(), &[text.to_vec()], "error");
+        let lines: Vec<usize> = run.matches.iter().map(|found| found.line_number).collect();
+        assert_eq!(lines, vec![1, 3, 4], "{} reported other lines", mode);
+        assert_eq!(run.lines, 4, "{} scanned a different number of lines", mode);
+    }
+}
+
+/** Yields `prefix`, then fails as a disk or a socket might. */
+struct FailingReader {
+    prefix: &'static [u8],
+}
+
+impl std::io::Read for FailingReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.prefix.is_empty() {
+            return Err(std::io::Error::other("device gone"));
+        }
+        let read = self.prefix.len().min(buf.len());
+        buf[..read].copy_from_slice(&self.prefix[..read]);
+        self.prefix = &self.prefix[read..];
+        Ok(read)
+    }
+}
+
+#[test]
+fn open_and_read_errors_are_reported_by_every_strategy() {
+    for (mode, strategy) in strategies(1, 2) {
+        let sources = vec![
+            Source::bytes("present", b"error one\n"),
+            Source::path("/nonexistent/grep-equivalence-missing.txt"),
+            Source::reader(
+                "failing",
+                FailingReader {
+                    prefix: b"error two\n",
+                },
+            ),
+        ];
+        let matcher = Matcher::new("error", MatchOptions::default());
+        let output = CollectOutput::new();
+        let report = strategy.search_report(sources, &matcher, &output);
+
+        let failed: Vec<(usize, &str)> = report
+            .errors
+            .iter()
+            .map(|failed| (failed.source, failed.name.as_str()))
+            .collect();
+        assert_eq!(
+            failed,
+            vec![
+                (1, "/nonexistent/grep-equivalence-missing.txt"),
+                (2, "failing")
+            ],
+            "{} reported other errors",
+            mode
+        );
+        // Lines read before a failure are still searched
+        assert_eq!(output.into_matches().len(), 2, "{} lost matches", mode);
+    }
+}
@@ -1,2 +1 @@
-pub mod not_found_response;
 pub mod pi;
@@ -1,41 +1,33 @@
-mod http;
 mod response;
-mod server;
 
-use http::http_status_code::HttpStatusCode;
 use response::pi::leibniz;
-use server::web_server::WebServer;
-use std::time::Instant;
+use std::{io, time::Instant};
+use webserver::{
+    http::http_status_code::HttpStatusCode,
+    server::{execution::ThreadPerConnection, web_server::WebServer},
+};
 
-fn main() {
+fn main() -> io::Result<()> {
     let mut server = WebServer::new();
     server.get("/", |context| {
         context.set_status(HttpStatusCode::Ok);
         context.send_text("Hello World");
     });
-    server.get("/pi/:a", |context| {
+    server.get("/pi/{n:u64}", |context| {
         let start = Instant::now();
-        let number = context.get_request().get_param("a").unwrap();
-        let number = number.parse::<u64>();
-        match number {
-            Err(_) => {
-                context.set_status(HttpStatusCode::BadRequest);
-                context.send_text("Invalid number");
-            }
-            Ok(number) => {
-                context.set_status(HttpStatusCode::Ok);
-                let pi = leibniz(number);
-                let elapsed = start.elapsed().as_secs_f64();
-                let result = format!(
-                    "Valor de pi para el termino {}: {} (Tiempo: {})",
-                    number,
-                    pi.to_string(),
-                    &elapsed.to_string()
-                );
-                context.send_text(&*result);
-            }
-        }
+        let number = context.param::<u64>("n").unwrap();
+        context.set_status(HttpStatusCode::Ok);
+        let pi = leibniz(number);
+        let elapsed = start.elapsed().as_secs_f64();
+        let result = format!(
+            "Valor de pi para el termino {}: {} (Tiempo: {})",
+            number,
+            pi,
+            &elapsed.to_string()
+        );
+        context.send_text(&result);
     });
-    let server = server;
-    server.serve(5000);
+    server.execution(ThreadPerConnection);
+    server.handle_signals();
+    server.serve("127.0.0.1:5000")
 }
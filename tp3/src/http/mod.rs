@@ -1,2 +0,0 @@
-pub mod http_method;
-pub mod http_status_code;
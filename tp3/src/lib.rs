@@ -2,9 +2,7 @@ pub fn add(left: u64, right: u64) -> u64 {
     left + right
 }
 
-pub mod http;
 pub mod response;
-pub mod server;
 
 #[cfg(test)]
 mod tests {
@@ -1,37 +1,31 @@
-use std::time::Instant;
-use tp3::{
-    http::http_status_code::HttpStatusCode, response::pi::leibniz, server::web_server::WebServer,
+use std::{io, time::Instant};
+use tp3::response::pi::leibniz;
+use webserver::{
+    http::http_status_code::HttpStatusCode,
+    server::{execution::FixedPool, web_server::WebServer},
 };
 
-fn main() {
+fn main() -> io::Result<()> {
     let mut server = WebServer::new();
     server.get("/", |context| {
         context.set_status(HttpStatusCode::Ok);
         context.send_text("Hello World");
     });
-    server.get("/pi/:a", |context| {
+    server.get("/pi/{n:u64}", |context| {
         let start = Instant::now();
-        let number = context.get_request().get_param("a").unwrap();
-        let number = number.parse::<u64>();
-        match number {
-            Err(_) => {
-                context.set_status(HttpStatusCode::BadRequest);
-                context.send_text("Invalid number");
-            }
-            Ok(number) => {
-                context.set_status(HttpStatusCode::Ok);
-                let pi = leibniz(number);
-                let elapsed = start.elapsed().as_secs_f64();
-                let result = format!(
-                    "Valor de pi para el termino {}: {} (Tiempo: {})",
-                    number,
-                    pi.to_string(),
-                    &elapsed.to_string()
-                );
-                context.send_text(&result);
-            }
-        }
+        let number = context.param::<u64>("n").unwrap();
+        context.set_status(HttpStatusCode::Ok);
+        let pi = leibniz(number);
+        let elapsed = start.elapsed().as_secs_f64();
+        let result = format!(
+            "Valor de pi para el termino {}: {} (Tiempo: {})",
+            number,
+            pi,
+            &elapsed.to_string()
+        );
+        context.send_text(&result);
     });
-    server.threads(16);
-    server.serve(5000);
+    server.execution(FixedPool { threads: 16 });
+    server.handle_signals();
+    server.serve("127.0.0.1:5000")
 }
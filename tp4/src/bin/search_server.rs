@@ -0,0 +1,169 @@
+use grep::{
+    matcher::{MatchOptions, Matcher},
+    output::{CollectOutput, Match},
+    search::strategy_from_mode,
+    source::Source,
+};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio::sync::Semaphore;
+use webserver::{http::http_status_code::HttpStatusCode, server::web_server::WebServer};
+
+/** Lists every regular file under `dir`, recursively, in a stable order.
+Symbolic links to files are listed, but links to directories are not followed,
+so a link back up the tree cannot make the walk loop forever. */
+fn corpus_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = vec![];
+    let Ok(entries) = fs::read_dir(dir) else {
+        return files;
+    };
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        if file_type.is_dir() {
+            files.extend(corpus_files(&path));
+        } else if file_type.is_file() || (file_type.is_symlink() && path.is_file()) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    files
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn match_json(found: &Match, corpus: &Path) -> String {
+    let file = Path::new(&found.name)
+        .strip_prefix(corpus)
+        .unwrap_or(Path::new(&found.name));
+    format!(
+        "{{\"file\":{},\"line\":{},\"text\":{},\"start\":{},\"end\":{}}}",
+        json_string(&file.to_string_lossy()),
+        found.line_number,
+        json_string(&found.line),
+        found.span.start,
+        found.span.end
+    )
+}
+
+fn main() -> io::Result<()> {
+    let args: Vec<String> = env::args().collect();
+    let corpus = args
+        .iter()
+        .position(|x| x == "--dir")
+        .map(|i| args[i + 1].clone())
+        .unwrap_or("./logs".to_string());
+    let host = args
+        .iter()
+        .position(|x| x == "--host")
+        .map(|i| args[i + 1].clone())
+        .unwrap_or("127.0.0.1".to_string());
+    let port: u16 = args
+        .iter()
+        .position(|x| x == "--port")
+        .map(|i| args[i + 1].parse().unwrap())
+        .unwrap_or(5001);
+    let max_searches = args
+        .iter()
+        .position(|x| x == "--max-searches")
+        .map(|i| args[i + 1].parse().unwrap())
+        .unwrap_or(4);
+
+    let corpus = PathBuf::from(corpus);
+    let semaphore = Arc::new(Semaphore::new(max_searches));
+    let mut server = WebServer::new();
+    server.get(
+        "/search",
+        move |context| {
+            let request = context.get_request();
+            let Some(pattern) = request.query("q").filter(|q| !q.is_empty()).map(str::to_string) else {
+                context.set_status(HttpStatusCode::BadRequest);
+                context.send_text("Missing q parameter");
+                return;
+            };
+            // The pipeline runs on a fixed number of threads, however many files the corpus has
+            let mode = request
+                .query("mode")
+                .unwrap_or("pipe")
+                .to_string();
+            let Some(strategy) = strategy_from_mode(&mode) else {
+                context.set_status(HttpStatusCode::BadRequest);
+                context.send_text(&format!("Unknown mode: {}", mode));
+                return;
+            };
+            let Ok(max) = request
+                .query("max")
+                .map_or(Ok(100), |max| max.parse::<usize>())
+            else {
+                context.set_status(HttpStatusCode::BadRequest);
+                context.send_text("Invalid max parameter");
+                return;
+            };
+            let options = MatchOptions {
+                ignore_case: request
+                    .query("ignore_case")
+                    .is_some_and(|value| value == "true" || value == "1"),
+                ..MatchOptions::default()
+            };
+
+            let Ok(_permit) = semaphore.clone().try_acquire_owned() else {
+                context.set_status(HttpStatusCode::TooManyRequests);
+                context.send_text("Too many searches in progress");
+                return;
+            };
+
+            let sources = corpus_files(&corpus)
+                .iter()
+                .map(|path| Source::path(path.to_string_lossy()))
+                .collect();
+            let matcher = Matcher::new(&pattern, options);
+            let output = CollectOutput::with_limit(max);
+            let report = strategy.search_report(sources, &matcher, &output);
+
+            // Only the first `max` matches, in file and line order, are kept
+            let total = output.total();
+            let matches = output.into_matches();
+            let matches: Vec<String> = matches
+                .iter()
+                .map(|found| match_json(found, &corpus))
+                .collect();
+
+            context.set_status(HttpStatusCode::Ok);
+            context.set_content_type("application/json");
+            context.send_text(&format!(
+                "{{\"query\":{},\"mode\":{},\"files\":{},\"total\":{},\"truncated\":{},\"elapsed_ms\":{},\"matches\":[{}]}}",
+                json_string(&pattern),
+                json_string(&mode),
+                report.files.len(),
+                total,
+                total > max,
+                report.elapsed.as_millis(),
+                matches.join(",")
+            ));
+        },
+    );
+    server.threads(16);
+    server.handle_signals();
+    server.serve((host.as_str(), port))
+}
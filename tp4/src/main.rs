@@ -1,104 +1,89 @@
-use grep::{SearchStrategy};
-use std::{fs, sync::Arc};
-use grep::search::{SequentialSearch};
-use tp4::{
-    http::http_status_code::HttpStatusCode,
-    server::web_server::WebServer,
-    structs::{multipart_parser::MultipartParser, shared_state::SharedState},
-};
+use grep::search::SequentialSearch;
+use grep::{SearchStrategy, source::Source};
+use std::{io, sync::Arc, time::Duration};
+use tp4::structs::{multipart_parser::MultipartParser, shared_state::SharedState};
+use webserver::{http::http_status_code::HttpStatusCode, server::web_server::WebServer};
 
-fn main() {
+/** Largest log file /upload accepts; larger ones are answered with 413 Content Too Large. */
+const MAX_UPLOAD_SIZE: usize = 256 * 1024 * 1024;
+
+fn main() -> io::Result<()> {
     let shared_state = Arc::new(SharedState::new());
     let mut server = WebServer::new();
-    server.get(
-        "/",
-        Box::new(|context| {
-            context.set_status(HttpStatusCode::Ok);
-            context.send_text("Hello World");
-        }),
-    );
+    server.get("/", |context| {
+        context.set_status(HttpStatusCode::Ok);
+        context.send_text("Hello World");
+    });
     let state = Arc::clone(&shared_state);
-    server.post(
-        "/upload",
-        Box::new(move |context| {
-            let request_body = context.get_request().get_body();
-            let content_type = context.get_request().get_header("Content-Type");
-
-            let Some(content_type) = content_type else {
-                context.set_status(HttpStatusCode::BadRequest);
-                context.send_text("Missing Content-Type header");
-                return;
-            };
-
-            let Some(boundary) = MultipartParser::extract_boundary(content_type) else {
-                context.set_status(HttpStatusCode::BadRequest);
-                context.send_text("Missing boundary in Content-Type");
-                return;
-            };
-
-            let filename = MultipartParser::extract_filename(request_body, &boundary)
-                .unwrap_or_else(|| "unknown".to_string());
-
-            let Some(_permit) = state.try_start_processing() else {
-                {
-                    let mut stats = state.stats.write().unwrap();
-                    stats.add_file(&filename, 1);
-                }
-                context.set_status(HttpStatusCode::TooManyRequests);
-                context.send_text("Too many files being processed");
-                return;
-            };
+    server.post("/upload", move |context| {
+        let request_body = context.get_request().get_body();
+        let content_type = context.get_request().get_header("Content-Type");
 
-            match MultipartParser::extract_file_content(request_body, &boundary) {
-                Some(file_content) => {
-                    let temp_folder = "./temp";
-                    if let Err(e) = fs::create_dir_all(temp_folder) {
-                        context.set_status(HttpStatusCode::InternalServerError);
-                        context.send_text(&format!("Could not create temp folder: {}", e));
-                        return;
-                    }
+        let Some(content_type) = content_type else {
+            context.set_status(HttpStatusCode::BadRequest);
+            context.send_text("Missing Content-Type header");
+            return;
+        };
 
-                    let filename = filename.replace(|c: char| !c.is_ascii_alphanumeric(), "_"); // sanitize
-                    let temp_path = format!("{}/{}", temp_folder, filename);
+        let Some(boundary) = MultipartParser::extract_boundary(content_type) else {
+            context.set_status(HttpStatusCode::BadRequest);
+            context.send_text("Missing boundary in Content-Type");
+            return;
+        };
 
-                    if fs::write(&temp_path, &file_content).is_err() {
-                        context.set_status(HttpStatusCode::InternalServerError);
-                        context.send_text("Error writing temporary file");
-                        return;
-                    }
+        let filename = MultipartParser::extract_filename(request_body, &boundary)
+            .unwrap_or_else(|| "unknown".to_string());
 
-                    let searcher = SequentialSearch;
-                    let count = searcher.search(&[temp_path.clone()], "exception");
+        let Some(_permit) = state.try_start_processing() else {
+            {
+                let mut stats = state.stats.write().unwrap();
+                stats.add_file(&filename, 1);
+            }
+            context.set_status(HttpStatusCode::TooManyRequests);
+            context.send_text("Too many files being processed");
+            return;
+        };
 
-                    {
-                        let mut stats = state.stats.write().unwrap();
-                        stats.add_file(&filename, count.try_into().unwrap());
-                    }
+        match MultipartParser::extract_file_content(request_body, &boundary) {
+            Some(file_content) => {
+                let filename = filename.replace(|c: char| !c.is_ascii_alphanumeric(), "_"); // sanitize
 
-                    let _ = fs::remove_file(&temp_path);
+                let searcher = SequentialSearch;
+                let count = searcher
+                    .search_sources(vec![Source::bytes(&filename, &file_content)], "exception");
 
-                    context.set_status(HttpStatusCode::Ok);
-                    context.send_text(&format!(
-                        "Processed file: {} with {} exceptions",
-                        filename, count
-                    ));
-                }
-                None => {
-                    context.set_status(HttpStatusCode::BadRequest);
-                    context.send_text("No file content found");
+                {
+                    let mut stats = state.stats.write().unwrap();
+                    stats.add_file(&filename, count.try_into().unwrap());
                 }
+
+                context.set_status(HttpStatusCode::Ok);
+                context.send_text(&format!(
+                    "Processed file: {} with {} exceptions",
+                    filename, count
+                ));
+            }
+            None => {
+                context.set_status(HttpStatusCode::BadRequest);
+                context.send_text("No file content found");
             }
-        }),
-    );
+        }
+    });
     let state_for_stats = Arc::clone(&shared_state);
-    server.get(
-        "/stats",
-        Box::new(move |context| {
-            let stats = state_for_stats.stats.read().unwrap();
-            context.set_status(HttpStatusCode::Ok);
-            context.send_text(&stats.as_string());
-        }),
-    );
+    server.get("/stats", move |context| {
+        let stats = state_for_stats.stats.read().unwrap();
+        context.set_status(HttpStatusCode::Ok);
+        context.send_text(&stats.as_string());
+    });
     server.threads(16);
-    server.serve(5000);
+    // Logs are uploaded whole, so allow far more than the 10 MiB default body, and the time to send it
+    server.max_body_size(MAX_UPLOAD_SIZE);
+    server.body_timeout(Duration::from_secs(300));
+    server.handle_signals();
+    server.serve("127.0.0.1:5000")?;
+
+    // Every upload has finished once serve returns, so these are the final numbers
+    let stats = shared_state.stats.read().unwrap();
+    println!("{}", stats.as_string());
+    Ok(())
 }
@@ -1 +0,0 @@
-pub mod not_found_response;
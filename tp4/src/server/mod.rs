@@ -1,5 +0,0 @@
-pub mod combinations;
-pub mod context;
-pub mod pair;
-pub mod request;
-pub mod web_server;
@@ -46,12 +46,11 @@ impl MultipartParser {
         let boundary_marker = format!("--{}", boundary);
         for part in body.split(&boundary_marker) {
             if let Some(disposition_line) = part.lines().find(|l| l.contains("Content-Disposition"))
+                && let Some(start) = disposition_line.find("filename=\"")
             {
-                if let Some(start) = disposition_line.find("filename=\"") {
-                    let rest = &disposition_line[start + 10..];
-                    if let Some(end) = rest.find('"') {
-                        return Some(rest[..end].to_string());
-                    }
+                let rest = &disposition_line[start + 10..];
+                if let Some(end) = rest.find('"') {
+                    return Some(rest[..end].to_string());
                 }
             }
         }
@@ -7,6 +7,12 @@ pub struct SharedState {
     pub semaphore: Arc<Semaphore>,
 }
 
+impl Default for SharedState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SharedState {
     pub fn new() -> Self {
         SharedState {
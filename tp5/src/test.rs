@@ -1,7 +1,6 @@
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::thread;
-use crate::queue::blocking::BlockingQueue;
 use crate::queue::Queue;
 
 pub(crate) fn run_blocking<Q: Queue<usize> + Send + Sync + 'static>(
@@ -15,8 +14,6 @@ pub(crate) fn run_blocking<Q: Queue<usize> + Send + Sync + 'static>(
 
     println!("-=-=- BLOCKING QUEUE -=-=-");
     thread::scope(|s| {
-        let queue = Arc::new(BlockingQueue::new());
-
         for producer_id in 0..producers {
             let prod_queue = Arc::clone(&queue);
             s.spawn(move || {
@@ -98,7 +95,7 @@ pub(crate) fn run_non_blocking<Q: Queue<usize> + Send + Sync + 'static>(
             let mut count = 0;
 
             while consumed_clone.load(Ordering::Acquire) < total_items {
-                if let Some(_) = q.dequeue() {
+                if q.dequeue().is_some() {
                     consumed_clone.fetch_add(1, Ordering::AcqRel);
                     count += 1;
                 } else {
@@ -0,0 +1,54 @@
+#[derive(Eq, Hash, PartialEq, Clone, Copy, Debug)]
+pub enum HttpMethod {
+    Get,
+    Post,
+    Put,
+    Delete,
+    Patch,
+    Head,
+    Options,
+    Connect,
+    Trace,
+}
+
+/** Every method, in the order they are listed in `Allow` headers. */
+pub const HTTP_METHODS: [HttpMethod; 9] = [
+    HttpMethod::Get,
+    HttpMethod::Head,
+    HttpMethod::Post,
+    HttpMethod::Put,
+    HttpMethod::Delete,
+    HttpMethod::Patch,
+    HttpMethod::Options,
+    HttpMethod::Connect,
+    HttpMethod::Trace,
+];
+
+pub fn http_method_from_string(method: &str) -> Option<HttpMethod> {
+    match method {
+        "GET" => Some(HttpMethod::Get),
+        "POST" => Some(HttpMethod::Post),
+        "PUT" => Some(HttpMethod::Put),
+        "DELETE" => Some(HttpMethod::Delete),
+        "PATCH" => Some(HttpMethod::Patch),
+        "HEAD" => Some(HttpMethod::Head),
+        "OPTIONS" => Some(HttpMethod::Options),
+        "CONNECT" => Some(HttpMethod::Connect),
+        "TRACE" => Some(HttpMethod::Trace),
+        _ => None,
+    }
+}
+
+pub fn http_method_to_string(method: &HttpMethod) -> &'static str {
+    match method {
+        HttpMethod::Get => "GET",
+        HttpMethod::Post => "POST",
+        HttpMethod::Put => "PUT",
+        HttpMethod::Delete => "DELETE",
+        HttpMethod::Patch => "PATCH",
+        HttpMethod::Head => "HEAD",
+        HttpMethod::Options => "OPTIONS",
+        HttpMethod::Connect => "CONNECT",
+        HttpMethod::Trace => "TRACE",
+    }
+}
@@ -0,0 +1,152 @@
+/** Declares every status code once, with its variant name and reason phrase. */
+macro_rules! status_codes {
+    ($($variant:ident = $code:literal, $reason:literal;)*) => {
+        /** Status codes from RFC 9110, plus the widely used ones from RFC 6585.
+        Any other code in the 100-599 range can be sent as `Custom`, built with `from_u16`. */
+        #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+        pub enum HttpStatusCode {
+            $($variant,)*
+            /** A code without a variant of its own, sent with an empty reason phrase. */
+            Custom(CustomCode),
+        }
+
+        impl HttpStatusCode {
+            /** The numeric code sent in the status line. */
+            pub fn code(&self) -> u16 {
+                match self {
+                    $(HttpStatusCode::$variant => $code,)*
+                    HttpStatusCode::Custom(code) => code.0,
+                }
+            }
+
+            /** The reason phrase sent after the code in the status line. */
+            pub fn reason_phrase(&self) -> &'static str {
+                match self {
+                    $(HttpStatusCode::$variant => $reason,)*
+                    HttpStatusCode::Custom(_) => "",
+                }
+            }
+
+            /** Gets the status for a code, as `Custom` when it has no variant.
+            Returns None for numbers outside the 100-599 range. */
+            pub fn from_u16(code: u16) -> Option<HttpStatusCode> {
+                match code {
+                    $($code => Some(HttpStatusCode::$variant),)*
+                    100..=599 => Some(HttpStatusCode::Custom(CustomCode(code))),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+/** A code in the 100-599 range that has no variant of its own.
+Only `HttpStatusCode::from_u16` builds one, so it never holds a named or invalid code. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CustomCode(u16);
+
+status_codes! {
+    Continue = 100, "Continue";
+    SwitchingProtocols = 101, "Switching Protocols";
+    Ok = 200, "OK";
+    Created = 201, "Created";
+    Accepted = 202, "Accepted";
+    NonAuthoritativeInformation = 203, "Non-Authoritative Information";
+    NoContent = 204, "No Content";
+    ResetContent = 205, "Reset Content";
+    PartialContent = 206, "Partial Content";
+    MultipleChoices = 300, "Multiple Choices";
+    MovedPermanently = 301, "Moved Permanently";
+    Found = 302, "Found";
+    SeeOther = 303, "See Other";
+    NotModified = 304, "Not Modified";
+    UseProxy = 305, "Use Proxy";
+    TemporaryRedirect = 307, "Temporary Redirect";
+    PermanentRedirect = 308, "Permanent Redirect";
+    BadRequest = 400, "Bad Request";
+    Unauthorized = 401, "Unauthorized";
+    PaymentRequired = 402, "Payment Required";
+    Forbidden = 403, "Forbidden";
+    NotFound = 404, "Not Found";
+    MethodNotAllowed = 405, "Method Not Allowed";
+    NotAcceptable = 406, "Not Acceptable";
+    ProxyAuthenticationRequired = 407, "Proxy Authentication Required";
+    RequestTimeout = 408, "Request Timeout";
+    Conflict = 409, "Conflict";
+    Gone = 410, "Gone";
+    LengthRequired = 411, "Length Required";
+    PreconditionFailed = 412, "Precondition Failed";
+    ContentTooLarge = 413, "Content Too Large";
+    UriTooLong = 414, "URI Too Long";
+    UnsupportedMediaType = 415, "Unsupported Media Type";
+    RangeNotSatisfiable = 416, "Range Not Satisfiable";
+    ExpectationFailed = 417, "Expectation Failed";
+    MisdirectedRequest = 421, "Misdirected Request";
+    UnprocessableContent = 422, "Unprocessable Content";
+    UpgradeRequired = 426, "Upgrade Required";
+    PreconditionRequired = 428, "Precondition Required";
+    TooManyRequests = 429, "Too Many Requests";
+    RequestHeaderFieldsTooLarge = 431, "Request Header Fields Too Large";
+    InternalServerError = 500, "Internal Server Error";
+    NotImplemented = 501, "Not Implemented";
+    BadGateway = 502, "Bad Gateway";
+    ServiceUnavailable = 503, "Service Unavailable";
+    GatewayTimeout = 504, "Gateway Timeout";
+    HttpVersionNotSupported = 505, "HTTP Version Not Supported";
+    NetworkAuthenticationRequired = 511, "Network Authentication Required";
+}
+
+impl HttpStatusCode {
+    /** 1xx: the request was received and is being processed. */
+    pub fn is_informational(&self) -> bool {
+        (100..200).contains(&self.code())
+    }
+
+    /** 2xx: the request was handled successfully. */
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.code())
+    }
+
+    /** 3xx: the client must go somewhere else to complete the request. */
+    pub fn is_redirection(&self) -> bool {
+        (300..400).contains(&self.code())
+    }
+
+    /** 4xx: the request was wrong. */
+    pub fn is_client_error(&self) -> bool {
+        (400..500).contains(&self.code())
+    }
+
+    /** 5xx: the server failed to handle a valid request. */
+    pub fn is_server_error(&self) -> bool {
+        (500..600).contains(&self.code())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_round_trip() {
+        assert_eq!(
+            HttpStatusCode::from_u16(404),
+            Some(HttpStatusCode::NotFound)
+        );
+        assert_eq!(HttpStatusCode::NotFound.reason_phrase(), "Not Found");
+        let custom = HttpStatusCode::from_u16(299).unwrap();
+        assert!(matches!(custom, HttpStatusCode::Custom(_)));
+        assert_eq!(custom.code(), 299);
+        assert_eq!(custom.reason_phrase(), "");
+        assert_eq!(HttpStatusCode::from_u16(42), None);
+        assert_eq!(HttpStatusCode::from_u16(1000), None);
+        // Named codes never come back as `Custom`, so they compare equal to their variant
+        assert_eq!(
+            HttpStatusCode::from_u16(204),
+            Some(HttpStatusCode::NoContent)
+        );
+        assert!(HttpStatusCode::TooManyRequests.is_client_error());
+        assert!(!HttpStatusCode::TooManyRequests.is_server_error());
+        assert!(HttpStatusCode::PermanentRedirect.is_redirection());
+    }
+}
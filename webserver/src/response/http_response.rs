@@ -0,0 +1,165 @@
+use crate::http::http_status_code::HttpStatusCode;
+
+/** A response ready to be written to a connection. */
+#[derive(Clone)]
+pub struct HttpResponse {
+    pub status: HttpStatusCode,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /** An empty response that must not be cached. */
+    pub fn new(status: HttpStatusCode) -> HttpResponse {
+        HttpResponse {
+            status,
+            headers: vec![(
+                "Cache-Control".to_string(),
+                "no-store, no-cache, must-revalidate".to_string(),
+            )],
+            body: Vec::new(),
+        }
+    }
+
+    /** A plain text response. */
+    pub fn text(status: HttpStatusCode, text: &str) -> HttpResponse {
+        HttpResponse::new(status)
+            .with_header("Content-Type", "text/plain")
+            .with_body(text)
+    }
+
+    /** Sets a header, replacing any previous value with the same name. */
+    pub fn with_header(mut self, name: &str, value: &str) -> HttpResponse {
+        self.set_header(name, value);
+        self
+    }
+
+    /** Sets the body, which may be any bytes. */
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> HttpResponse {
+        self.body = body.into();
+        self
+    }
+
+    /** Gets the first value of a header, ignoring the case of its name. */
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(existing, _)| existing.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /** Sets a header, replacing any previous value with the same name. */
+    pub fn set_header(&mut self, name: &str, value: &str) {
+        self.remove_header(name);
+        self.append_header(name, value);
+    }
+
+    /** Adds a header, keeping previous values with the same name, as needed for `Set-Cookie`.
+    Panics if the name or value could break the framing of the response. */
+    pub fn append_header(&mut self, name: &str, value: &str) {
+        let valid_name = !name.is_empty()
+            && name
+                .bytes()
+                .all(|byte| byte.is_ascii_graphic() && !b"()<>@,;:\\\"/[]?={}".contains(&byte));
+        if !valid_name {
+            panic!("Invalid header name {:?}", name);
+        }
+        if value.contains(['\r', '\n', '\0']) {
+            panic!("Invalid value for header {}", name);
+        }
+        self.headers.push((name.to_string(), value.to_string()));
+    }
+
+    /** Removes every value of a header. */
+    pub fn remove_header(&mut self, name: &str) {
+        self.headers
+            .retain(|(existing, _)| !existing.eq_ignore_ascii_case(name));
+    }
+
+    /** Serializes the response. `Content-Length` always describes the body,
+    even when it is left out as in answers to HEAD requests.
+    1xx, 204 and 304 responses never have a body, so they are sent without either. */
+    pub fn to_bytes(&self, include_body: bool) -> Vec<u8> {
+        let bodiless = self.status.is_informational()
+            || matches!(
+                self.status,
+                HttpStatusCode::NoContent | HttpStatusCode::NotModified
+            );
+        let mut head = self.status_and_headers(&["content-length", "transfer-encoding"]);
+        if !bodiless {
+            head.push_str(&format!("Content-Length: {}\r\n", self.body.len()));
+        }
+        head.push_str("\r\n");
+
+        let mut bytes = head.into_bytes();
+        if include_body && !bodiless {
+            bytes.extend_from_slice(&self.body);
+        }
+        bytes
+    }
+
+    /** Serializes the status line and headers of a response whose body follows separately.
+    A body of known `length` is framed by `Content-Length`, any other by its own
+    `Transfer-Encoding` or by closing the connection. */
+    pub fn head_bytes(&self, length: Option<u64>) -> Vec<u8> {
+        let mut head = self.status_and_headers(&["content-length"]);
+        if let Some(length) = length {
+            head.push_str(&format!("Content-Length: {}\r\n", length));
+        }
+        head.push_str("\r\n");
+        head.into_bytes()
+    }
+
+    /** The status line and headers, leaving out the `framing` headers that only the
+    serializer can set correctly. */
+    fn status_and_headers(&self, framing: &[&str]) -> String {
+        let mut head = format!(
+            "HTTP/1.1 {} {}\r\n",
+            self.status.code(),
+            self.status.reason_phrase()
+        );
+        for (name, value) in &self.headers {
+            if framing
+                .iter()
+                .any(|framed| name.eq_ignore_ascii_case(framed))
+            {
+                continue;
+            }
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        head
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_headers_and_binary_bodies() {
+        let mut response = HttpResponse::new(HttpStatusCode::Ok)
+            .with_header("Content-Type", "application/octet-stream")
+            .with_header("Content-Length", "999")
+            .with_body(vec![0, 159, 146, 150]);
+        response.append_header("Set-Cookie", "a=1");
+        response.append_header("Set-Cookie", "b=2");
+        response.set_header("cache-control", "max-age=60");
+
+        let mut expected = b"HTTP/1.1 200 OK\r\n\
+Content-Type: application/octet-stream\r\n\
+Set-Cookie: a=1\r\n\
+Set-Cookie: b=2\r\n\
+cache-control: max-age=60\r\n\
+Content-Length: 4\r\n\r\n"
+            .to_vec();
+        assert_eq!(response.to_bytes(false), expected);
+        expected.extend_from_slice(&[0, 159, 146, 150]);
+        assert_eq!(response.to_bytes(true), expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid value for header X-Injected")]
+    fn rejects_header_injection() {
+        HttpResponse::new(HttpStatusCode::Ok).set_header("X-Injected", "a\r\nSet-Cookie: b=2");
+    }
+}
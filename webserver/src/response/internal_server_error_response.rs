@@ -0,0 +1,7 @@
+use super::http_response::HttpResponse;
+use crate::http::http_status_code::HttpStatusCode;
+
+/** Answers a request whose handler panicked before writing a response. */
+pub fn internal_server_error_response() -> HttpResponse {
+    HttpResponse::text(HttpStatusCode::InternalServerError, "Internal Server Error")
+}
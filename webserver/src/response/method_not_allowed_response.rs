@@ -0,0 +1,9 @@
+use super::http_response::HttpResponse;
+use crate::http::http_status_code::HttpStatusCode;
+
+/** Answers a request whose path exists under other methods, listed in `allow`. */
+pub fn method_not_allowed_response(allow: &str) -> HttpResponse {
+    let mut response = HttpResponse::text(HttpStatusCode::MethodNotAllowed, "Method Not Allowed");
+    response.set_header("Allow", allow);
+    response
+}
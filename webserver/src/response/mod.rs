@@ -0,0 +1,6 @@
+pub mod bad_request_response;
+pub mod http_response;
+pub mod internal_server_error_response;
+pub mod method_not_allowed_response;
+pub mod not_found_response;
+pub mod options_response;
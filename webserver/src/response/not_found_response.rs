@@ -0,0 +1,6 @@
+use super::http_response::HttpResponse;
+use crate::http::http_status_code::HttpStatusCode;
+
+pub fn not_found_response() -> HttpResponse {
+    HttpResponse::text(HttpStatusCode::NotFound, "Not Found")
+}
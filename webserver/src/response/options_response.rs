@@ -0,0 +1,9 @@
+use super::http_response::HttpResponse;
+use crate::http::http_status_code::HttpStatusCode;
+
+/** Answers an OPTIONS request for a path that has no OPTIONS handler of its own. */
+pub fn options_response(allow: &str) -> HttpResponse {
+    let mut response = HttpResponse::new(HttpStatusCode::NoContent);
+    response.set_header("Allow", allow);
+    response
+}
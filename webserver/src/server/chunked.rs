@@ -0,0 +1,78 @@
+use super::connection::{Limits, ParseError, read_headers, read_line, trim_line};
+use std::{collections::HashMap, io::BufRead};
+
+/** Reads a body sent with `Transfer-Encoding: chunked`, up to and including its trailer.
+Trailer fields are added to `headers`. The decoded body may not exceed `limits.max_body_size`. */
+pub(crate) fn read_chunked_body(
+    reader: &mut impl BufRead,
+    headers: &mut HashMap<String, String>,
+    limits: &Limits,
+) -> Result<Vec<u8>, ParseError> {
+    let mut body = Vec::new();
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let too_long = ParseError::Malformed("Chunk size line is too long");
+        if read_line(reader, &mut line, limits.max_request_line, too_long)? == 0 {
+            return Err(ParseError::Malformed("Unterminated chunked body"));
+        }
+        // Chunk extensions after `;` carry nothing we use
+        let size = trim_line(&line)?.split(';').next().unwrap_or("").trim();
+        let size = Some(size)
+            .filter(|size| size.bytes().all(|byte| byte.is_ascii_hexdigit()))
+            .and_then(|size| usize::from_str_radix(size, 16).ok())
+            .ok_or(ParseError::Malformed("Invalid chunk size"))?;
+        if size == 0 {
+            break;
+        }
+        if size > limits.max_body_size - body.len() {
+            return Err(ParseError::ContentTooLarge);
+        }
+
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..])?;
+
+        line.clear();
+        let too_long = ParseError::Malformed("Chunk is longer than its size");
+        read_line(reader, &mut line, 2, too_long)?;
+        if line != b"\r\n" && line != b"\n" {
+            return Err(ParseError::Malformed("Chunk is longer than its size"));
+        }
+    }
+
+    let mut trailers = HashMap::new();
+    read_headers(reader, &mut trailers, limits)?;
+    // Trailers may not override the fields that framed the message
+    trailers.retain(|name, _| !matches!(name.as_str(), "content-length" | "transfer-encoding"));
+    headers.extend(trailers);
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_chunks_and_trailers() {
+        let mut input: &[u8] = b"4\r\nWiki\r\n6;ext=1\r\npedia \r\nE\r\nin \r\n\r\nchunks.\r\n0\r\nExpires: never\r\n\r\nrest";
+        let mut headers = HashMap::new();
+        let body = read_chunked_body(&mut input, &mut headers, &Limits::default()).unwrap();
+        assert_eq!(body, b"Wikipedia in \r\n\r\nchunks.");
+        assert_eq!(headers["expires"], "never");
+        assert_eq!(input, b"rest");
+    }
+
+    #[test]
+    fn rejects_malformed_chunks() {
+        for input in [
+            &b"zz\r\nabc\r\n0\r\n\r\n"[..],
+            b"2\r\nabc\r\n0\r\n\r\n",
+            b"3\r\nabc\r\n",
+        ] {
+            let mut input = input;
+            let result = read_chunked_body(&mut input, &mut HashMap::new(), &Limits::default());
+            assert!(matches!(result, Err(ParseError::Malformed(_))));
+        }
+    }
+}
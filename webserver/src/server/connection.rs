@@ -0,0 +1,538 @@
+use super::chunked::read_chunked_body;
+use crate::{
+    http::{
+        http_method::{HttpMethod, http_method_from_string},
+        http_status_code::HttpStatusCode,
+    },
+    response::{bad_request_response::bad_request_response, http_response::HttpResponse},
+};
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, ErrorKind},
+    time::Duration,
+};
+
+/** How long connections may stay open, how much they may be reused, and how large
+and how slow the requests they carry may be. */
+#[derive(Clone, Copy)]
+pub struct Limits {
+    /** Requests answered on a single connection before it is closed. */
+    pub max_requests: usize,
+    /** How long a kept-alive connection may wait for its next request. */
+    pub idle_timeout: Duration,
+    /** How long a request line and its headers may take to arrive once started. */
+    pub header_timeout: Duration,
+    /** How long a request body may take to arrive once its headers are read. */
+    pub body_timeout: Duration,
+    /** How long writing a response may block before the connection is dropped. */
+    pub write_timeout: Duration,
+    /** Bytes per second a client must keep up while sending a request, or 0 for no minimum. */
+    pub min_transfer_rate: u64,
+    /** Bytes allowed in the request line, answered with 414 when exceeded. */
+    pub max_request_line: usize,
+    /** Bytes allowed in all header lines together, answered with 431 when exceeded. */
+    pub max_header_size: usize,
+    /** Header lines allowed in a request, answered with 431 when exceeded. */
+    pub max_headers: usize,
+    /** Bytes allowed in a request body, answered with 413 when exceeded. */
+    pub max_body_size: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_requests: 100,
+            idle_timeout: Duration::from_secs(5),
+            header_timeout: Duration::from_secs(10),
+            body_timeout: Duration::from_secs(30),
+            write_timeout: Duration::from_secs(10),
+            min_transfer_rate: 512,
+            max_request_line: 8 * 1024,
+            max_header_size: 16 * 1024,
+            max_headers: 100,
+            max_body_size: 10 * 1024 * 1024,
+        }
+    }
+}
+
+/** Why a request could not be read from a connection. */
+#[derive(Debug)]
+pub enum ParseError {
+    /** The connection failed or closed in the middle of a request. */
+    Io(io::Error),
+    /** The request does not follow HTTP/1.1 framing. */
+    Malformed(&'static str),
+    /** The request has a body but does not say how long it is. */
+    LengthRequired,
+    /** The client took too long to send the request. */
+    Timeout,
+    /** The request line is longer than `Limits::max_request_line`. */
+    UriTooLong,
+    /** The headers exceed `Limits::max_header_size` or `Limits::max_headers`. */
+    HeaderFieldsTooLarge,
+    /** The body is longer than `Limits::max_body_size`. */
+    ContentTooLarge,
+    /** The method or transfer coding is well formed but not supported. */
+    NotImplemented(&'static str),
+    /** The request is not HTTP/1.0 or HTTP/1.1. */
+    VersionNotSupported,
+}
+
+impl From<io::Error> for ParseError {
+    fn from(error: io::Error) -> Self {
+        match error.kind() {
+            ErrorKind::InvalidData => ParseError::Malformed("Request is not valid UTF-8"),
+            ErrorKind::UnexpectedEof => ParseError::Malformed("Request ended early"),
+            ErrorKind::WouldBlock | ErrorKind::TimedOut => ParseError::Timeout,
+            _ => ParseError::Io(error),
+        }
+    }
+}
+
+impl ParseError {
+    /** The response to send before closing the connection, if the client can still read one. */
+    pub fn response(&self) -> Option<HttpResponse> {
+        let (status, reason) = match self {
+            ParseError::Io(_) => return None,
+            ParseError::Malformed(reason) => {
+                let mut response = bad_request_response();
+                response.body = reason.as_bytes().to_vec();
+                return Some(response);
+            }
+            ParseError::LengthRequired => (HttpStatusCode::LengthRequired, "Length Required"),
+            ParseError::Timeout => (HttpStatusCode::RequestTimeout, "Request Timeout"),
+            ParseError::UriTooLong => (HttpStatusCode::UriTooLong, "URI Too Long"),
+            ParseError::HeaderFieldsTooLarge => (
+                HttpStatusCode::RequestHeaderFieldsTooLarge,
+                "Request Header Fields Too Large",
+            ),
+            ParseError::ContentTooLarge => (HttpStatusCode::ContentTooLarge, "Content Too Large"),
+            ParseError::NotImplemented(reason) => (HttpStatusCode::NotImplemented, *reason),
+            ParseError::VersionNotSupported => (
+                HttpStatusCode::HttpVersionNotSupported,
+                "HTTP Version Not Supported",
+            ),
+        };
+        Some(HttpResponse::text(status, reason))
+    }
+}
+
+/** A request as read from the connection, before routing. */
+pub(crate) struct RawRequest {
+    pub method: HttpMethod,
+    pub target: String,
+    pub version: String,
+    /** Header values by lowercase name. Repeated fields are joined with commas. */
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl RawRequest {
+    /** Whether the client wants the connection kept open after the response.
+    HTTP/1.1 keeps it open unless told otherwise, HTTP/1.0 only when asked to. */
+    pub fn keep_alive(&self) -> bool {
+        let has_token = |token: &str| {
+            self.headers.get("connection").is_some_and(|value| {
+                value
+                    .split(',')
+                    .any(|option| option.trim().eq_ignore_ascii_case(token))
+            })
+        };
+        match self.version.as_str() {
+            "HTTP/1.0" => has_token("keep-alive"),
+            _ => !has_token("close"),
+        }
+    }
+}
+
+/** Reads the next request from a connection, body included.
+Returns None when the client closes the connection, or stays idle past the read timeout,
+before sending anything. Requests pipelined behind this one stay in `reader`.
+The server reads the head and body separately, to give each its own deadline. */
+#[cfg(test)]
+pub(crate) fn read_request(
+    reader: &mut impl BufRead,
+    limits: &Limits,
+) -> Result<Option<RawRequest>, ParseError> {
+    let Some(mut request) = read_head(reader, limits)? else {
+        return Ok(None);
+    };
+    request.body = read_body(reader, request.method, &mut request.headers, limits)?;
+    Ok(Some(request))
+}
+
+/** Reads the request line and headers of the next request, leaving its body in `reader`.
+Lines are read incrementally and never buffered past the sizes in `limits`. */
+pub(crate) fn read_head(
+    reader: &mut impl BufRead,
+    limits: &Limits,
+) -> Result<Option<RawRequest>, ParseError> {
+    let mut line = Vec::new();
+    // Empty lines before a request line are ignored, but count towards its size
+    let mut budget = limits.max_request_line;
+    loop {
+        line.clear();
+        match read_line(reader, &mut line, budget, ParseError::UriTooLong) {
+            Ok(0) => return Ok(None),
+            Err(ParseError::Timeout) if line.is_empty() && budget == limits.max_request_line => {
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+            Ok(read) if trim_line(&line)?.is_empty() => budget -= read,
+            Ok(_) => break,
+        }
+    }
+    let (method, target, version) = parse_request_line(trim_line(&line)?)?;
+
+    let mut headers = HashMap::new();
+    read_headers(reader, &mut headers, limits)?;
+    if version == "HTTP/1.1" && !headers.contains_key("host") {
+        return Err(ParseError::Malformed("Missing Host header"));
+    }
+
+    Ok(Some(RawRequest {
+        method,
+        target,
+        version,
+        headers,
+        body: Vec::new(),
+    }))
+}
+
+/** Splits `GET /path HTTP/1.1` into its method, target and version. */
+fn parse_request_line(line: &str) -> Result<(HttpMethod, String, String), ParseError> {
+    let parts: Vec<&str> = line.split(' ').collect();
+    let [method, target, version] = parts[..] else {
+        return Err(ParseError::Malformed("Invalid request line"));
+    };
+    if !is_token(method) {
+        return Err(ParseError::Malformed("Invalid method"));
+    }
+    let Some(method) = http_method_from_string(method) else {
+        return Err(ParseError::NotImplemented("Unknown method"));
+    };
+
+    let version = match version.strip_prefix("HTTP/").map(str::as_bytes) {
+        Some([b'1', b'.', b'0']) => "HTTP/1.0",
+        // Later 1.x minor versions are answered as HTTP/1.1
+        Some([b'1', b'.', minor]) if minor.is_ascii_digit() => "HTTP/1.1",
+        Some([major, b'.', minor]) if major.is_ascii_digit() && minor.is_ascii_digit() => {
+            return Err(ParseError::VersionNotSupported);
+        }
+        _ => return Err(ParseError::Malformed("Invalid HTTP version")),
+    };
+
+    if target.is_empty() || !target.bytes().all(|byte| byte.is_ascii_graphic()) {
+        return Err(ParseError::Malformed("Invalid request target"));
+    }
+    let target = match target {
+        _ if target.starts_with('/') => target.to_string(),
+        "*" if method == HttpMethod::Options => target.to_string(),
+        _ if method == HttpMethod::Connect => target.to_string(),
+        _ => {
+            // Absolute form, as sent to proxies: only the path and query are kept
+            let Some((_, rest)) = target.split_once("://") else {
+                return Err(ParseError::Malformed("Invalid request target"));
+            };
+            match rest.find(['/', '?']) {
+                Some(start) if rest[start..].starts_with('?') => format!("/{}", &rest[start..]),
+                Some(start) => rest[start..].to_string(),
+                None => "/".to_string(),
+            }
+        }
+    };
+    Ok((method, target, version.to_string()))
+}
+
+/** Reads header lines up to the empty line that ends them, keyed by lowercase name.
+Repeated fields are joined with commas, as RFC 9110 allows. */
+pub(crate) fn read_headers(
+    reader: &mut impl BufRead,
+    headers: &mut HashMap<String, String>,
+    limits: &Limits,
+) -> Result<(), ParseError> {
+    let mut budget = limits.max_header_size;
+    let mut count = 0;
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let read = read_line(reader, &mut line, budget, ParseError::HeaderFieldsTooLarge)?;
+        if read == 0 {
+            return Err(ParseError::Malformed("Request ended inside its headers"));
+        }
+        budget -= read;
+        let header = trim_line(&line)?;
+        if header.is_empty() {
+            return Ok(());
+        }
+        count += 1;
+        if count > limits.max_headers {
+            return Err(ParseError::HeaderFieldsTooLarge);
+        }
+        if header.starts_with([' ', '\t']) {
+            return Err(ParseError::Malformed("Obsolete line folding"));
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            return Err(ParseError::Malformed("Invalid header line"));
+        };
+        // Whitespace before the colon has been used to smuggle requests
+        if !is_token(name) {
+            return Err(ParseError::Malformed("Invalid header name"));
+        }
+        let value = value.trim_matches([' ', '\t']);
+        if value.chars().any(|c| c.is_control() && c != '\t') {
+            return Err(ParseError::Malformed("Invalid header value"));
+        }
+
+        let name = name.to_ascii_lowercase();
+        match headers.get_mut(&name) {
+            Some(existing) if name == "content-length" || name == "host" => {
+                if existing != value {
+                    return Err(ParseError::Malformed("Conflicting repeated header"));
+                }
+            }
+            Some(existing) => {
+                existing.push_str(", ");
+                existing.push_str(value);
+            }
+            None => {
+                headers.insert(name, value.to_string());
+            }
+        }
+    }
+}
+
+/** Reads the body framed by either `Transfer-Encoding: chunked` or `Content-Length`. */
+pub(crate) fn read_body(
+    reader: &mut impl BufRead,
+    method: HttpMethod,
+    headers: &mut HashMap<String, String>,
+    limits: &Limits,
+) -> Result<Vec<u8>, ParseError> {
+    if let Some(encoding) = headers.get("transfer-encoding") {
+        // Both framings at once is how requests get smuggled past proxies
+        if headers.contains_key("content-length") {
+            return Err(ParseError::Malformed(
+                "Both Content-Length and Transfer-Encoding are set",
+            ));
+        }
+        let mut codings = encoding.split(',').map(str::trim);
+        if !codings
+            .next_back()
+            .is_some_and(|last| last.eq_ignore_ascii_case("chunked"))
+        {
+            return Err(ParseError::Malformed("Request body must end chunked"));
+        }
+        if codings.next().is_some() {
+            return Err(ParseError::NotImplemented("Unsupported Transfer-Encoding"));
+        }
+        return read_chunked_body(reader, headers, limits);
+    }
+
+    let content_length = match headers.get("content-length") {
+        Some(value) if !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_digit()) => {
+            value
+                .parse::<usize>()
+                .map_err(|_| ParseError::ContentTooLarge)?
+        }
+        Some(_) => return Err(ParseError::Malformed("Invalid Content-Length")),
+        None if matches!(
+            method,
+            HttpMethod::Post | HttpMethod::Put | HttpMethod::Patch
+        ) =>
+        {
+            return Err(ParseError::LengthRequired);
+        }
+        None => 0,
+    };
+    if content_length > limits.max_body_size {
+        return Err(ParseError::ContentTooLarge);
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    Ok(body)
+}
+
+/** Reads one line, line ending included, appending it to `line`, and returns its length,
+or 0 at the end of the input. Fails with `too_long` rather than buffering more than
+`limit` bytes, so a client cannot exhaust memory with a line that never ends. */
+pub(crate) fn read_line(
+    reader: &mut impl BufRead,
+    line: &mut Vec<u8>,
+    limit: usize,
+    too_long: ParseError,
+) -> Result<usize, ParseError> {
+    let mut read = 0;
+    loop {
+        let available = match reader.fill_buf() {
+            Ok(available) => available,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        if available.is_empty() {
+            return Ok(read);
+        }
+        let (used, ended) = match available.iter().position(|&byte| byte == b'\n') {
+            Some(end) => (end + 1, true),
+            None => (available.len(), false),
+        };
+        if read + used > limit {
+            return Err(too_long);
+        }
+        line.extend_from_slice(&available[..used]);
+        reader.consume(used);
+        read += used;
+        if ended {
+            return Ok(read);
+        }
+    }
+}
+
+/** Strips the CRLF, or bare LF, that ends `line`. A CR anywhere else is rejected. */
+pub(crate) fn trim_line(line: &[u8]) -> Result<&str, ParseError> {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    if line.contains(&b'\r') {
+        return Err(ParseError::Malformed("Bare CR in request"));
+    }
+    std::str::from_utf8(line).map_err(|_| ParseError::Malformed("Request is not valid UTF-8"))
+}
+
+/** Whether `value` is an HTTP token, the syntax of methods and header names. */
+fn is_token(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&byte))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pipelined_requests_are_read_in_order() {
+        let mut input: &[u8] = b"POST /a HTTP/1.1\r\nHost: a\r\nContent-Length: 3\r\n\r\nabc\
+GET /b HTTP/1.0\r\nConnection: keep-alive\r\n\r\n\
+GET /c HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n";
+
+        let first = read_request(&mut input, &Limits::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (first.method, first.target.as_str()),
+            (HttpMethod::Post, "/a")
+        );
+        assert_eq!(first.body, b"abc");
+        assert!(first.keep_alive());
+
+        let second = read_request(&mut input, &Limits::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(second.target, "/b");
+        assert!(second.keep_alive());
+
+        let third = read_request(&mut input, &Limits::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(third.target, "/c");
+        assert!(!third.keep_alive());
+
+        assert!(
+            read_request(&mut input, &Limits::default())
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn bodies_need_a_length() {
+        let mut input: &[u8] = b"POST /upload HTTP/1.1\r\nHost: a\r\n\r\n";
+        assert!(matches!(
+            read_request(&mut input, &Limits::default()),
+            Err(ParseError::LengthRequired)
+        ));
+
+        let mut input: &[u8] =
+            b"POST /upload HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n0\r\n\r\n";
+        assert_eq!(
+            read_request(&mut input, &Limits::default())
+                .unwrap()
+                .unwrap()
+                .body,
+            b"abc"
+        );
+    }
+
+    #[test]
+    fn http_1_0_closes_by_default() {
+        let mut input: &[u8] = b"GET / HTTP/1.0\r\n\r\n";
+        assert!(
+            !read_request(&mut input, &Limits::default())
+                .unwrap()
+                .unwrap()
+                .keep_alive()
+        );
+    }
+
+    #[test]
+    fn malformed_requests_are_rejected_without_panicking() {
+        let limits = Limits {
+            max_request_line: 32,
+            max_header_size: 64,
+            max_headers: 2,
+            max_body_size: 4,
+            ..Limits::default()
+        };
+        let parse = |input: &[u8]| {
+            let mut input = input;
+            read_request(&mut input, &limits)
+                .err()
+                .and_then(|error| error.response())
+                .map(|response| response.status.code())
+        };
+
+        assert_eq!(parse(b"GET / HTTP/1.1\r\nHost: a\r\n\r\n"), None);
+        assert_eq!(parse(b"GET\r\n\r\n"), Some(400));
+        assert_eq!(parse(b"GET  / HTTP/1.1\r\n\r\n"), Some(400));
+        assert_eq!(parse(b"GET / HTTP/1.1\r\n\r\n"), Some(400));
+        assert_eq!(parse(b"GET / HTTP/1.0\r\nBad Name: x\r\n\r\n"), Some(400));
+        assert_eq!(
+            parse(b"GET / HTTP/1.0\r\nA: 1\r\n folded\r\n\r\n"),
+            Some(400)
+        );
+        assert_eq!(
+            parse(b"GET / HTTP/1.0\r\nContent-Length: +1\r\n\r\nx"),
+            Some(400)
+        );
+        assert_eq!(parse(b"\xff / HTTP/1.0\r\n\r\n"), Some(400));
+        assert_eq!(parse(b"BREW / HTTP/1.0\r\n\r\n"), Some(501));
+        assert_eq!(parse(b"GET / HTTP/2.0\r\n\r\n"), Some(505));
+        let long_target = format!("GET /{} HTTP/1.0\r\n\r\n", "a".repeat(64));
+        assert_eq!(parse(long_target.as_bytes()), Some(414));
+        assert_eq!(
+            parse(b"GET / HTTP/1.0\r\nA: 1\r\nB: 2\r\nC: 3\r\n\r\n"),
+            Some(431)
+        );
+        let long_header = format!("GET / HTTP/1.0\r\nA: {}\r\n\r\n", "a".repeat(64));
+        assert_eq!(parse(long_header.as_bytes()), Some(431));
+        assert_eq!(
+            parse(b"POST / HTTP/1.0\r\nContent-Length: 5\r\n\r\nhello"),
+            Some(413)
+        );
+        assert_eq!(
+            parse(b"POST / HTTP/1.0\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n"),
+            Some(413)
+        );
+    }
+
+    #[test]
+    fn absolute_targets_keep_only_the_path() {
+        let mut input: &[u8] =
+            b"GET http://example.com/a?b=1 HTTP/1.1\r\nHost: example.com\r\n\r\n";
+        let request = read_request(&mut input, &Limits::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(request.target, "/a?b=1");
+    }
+}
@@ -0,0 +1,264 @@
+use super::{
+    request::Request,
+    response_stream::{Connection, ResponseStream},
+};
+use crate::{http::http_status_code::HttpStatusCode, response::http_response::HttpResponse};
+use std::{
+    io::{self, ErrorKind, Write},
+    str::FromStr,
+};
+
+pub struct Context {
+    request: Request,
+    response: HttpResponse,
+    /** Set once the response has been sent or started streaming; it cannot change afterwards. */
+    completed: bool,
+    streamed: bool,
+    connection: Option<Connection>,
+}
+
+impl Context {
+    pub fn new(request: Request, status: HttpStatusCode) -> Context {
+        Context {
+            request,
+            response: HttpResponse::text(status, ""),
+            completed: false,
+            streamed: false,
+            connection: None,
+        }
+    }
+
+    /** Gives the context the connection its response can be streamed to. */
+    pub(crate) fn attach(&mut self, connection: Connection) {
+        self.connection = Some(connection);
+    }
+
+    pub(crate) fn detach(&mut self) -> Option<Connection> {
+        self.connection.take()
+    }
+
+    /** Gets the request associated with the context. */
+    pub fn get_request(&self) -> &Request {
+        &self.request
+    }
+
+    /** Parses a path parameter, as in `context.param::<u64>("n")`.
+    Returns None when the parameter is missing or does not parse. */
+    pub fn param<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.request.get_param(key)?.parse().ok()
+    }
+
+    /** Gets the status code of the response. */
+    pub fn get_status(&self) -> &HttpStatusCode {
+        &self.response.status
+    }
+
+    /** Sets the status code of the response. */
+    pub fn set_status(&mut self, status: HttpStatusCode) {
+        if self.streamed {
+            panic!("Cannot set status code after response has been sent");
+        }
+        self.response.status = status;
+    }
+
+    /** Sets the Content-Type of the response, `text/plain` by default. */
+    pub fn set_content_type(&mut self, content_type: &str) {
+        self.set_header("Content-Type", content_type);
+    }
+
+    /** Gets the Content-Type of the response. */
+    pub fn get_content_type(&self) -> &str {
+        self.response.header("Content-Type").unwrap_or("")
+    }
+
+    /** Sets a response header, replacing any previous value with the same name.
+    Headers can change until the response is written, so middleware may add them
+    after the handler has sent its body. */
+    pub fn set_header(&mut self, name: &str, value: &str) {
+        if self.streamed {
+            panic!("Cannot set header after response has been sent");
+        }
+        self.response.set_header(name, value);
+    }
+
+    /** Adds a response header, keeping previous values with the same name. */
+    pub fn append_header(&mut self, name: &str, value: &str) {
+        if self.streamed {
+            panic!("Cannot set header after response has been sent");
+        }
+        self.response.append_header(name, value);
+    }
+
+    /** Gets the first value of a response header. */
+    pub fn get_response_header(&self, name: &str) -> Option<&str> {
+        self.response.header(name)
+    }
+
+    /** Sends a text response to the client, completing the context. */
+    pub fn send_text(&mut self, text: &str) {
+        self.send_bytes(text);
+    }
+
+    /** Sends a binary response to the client, completing the context.
+    The content type stays as set, `text/plain` unless changed. */
+    pub fn send_bytes(&mut self, body: impl Into<Vec<u8>>) {
+        if self.completed {
+            panic!("Cannot send response after response has been sent");
+        }
+        self.completed = true;
+        self.response.body = body.into();
+    }
+
+    /** Replaces the whole response, status and headers included, completing the context. */
+    pub fn send(&mut self, response: HttpResponse) {
+        if self.completed {
+            panic!("Cannot send response after response has been sent");
+        }
+        self.completed = true;
+        self.response = response;
+    }
+
+    /** Gets the body set through send_text, send_bytes or send */
+    pub fn get_body(&self) -> &[u8] {
+        &self.response.body
+    }
+
+    /** Sends the status and headers right away and returns a writer for the body,
+    completing the context. The body is sent with chunked encoding, or to HTTP/1.0
+    clients by closing the connection once it ends. */
+    pub fn stream(&mut self) -> io::Result<ResponseStream<'_>> {
+        self.start_stream(None)
+    }
+
+    /** Like `stream`, for a body of exactly `length` bytes. It is sent as is with a
+    `Content-Length`, so clients know its size up front and HTTP/1.0 connections stay open.
+    Writing more than `length` bytes fails; ending the body short closes the connection. */
+    pub fn stream_sized(&mut self, length: u64) -> io::Result<ResponseStream<'_>> {
+        self.start_stream(Some(length))
+    }
+
+    fn start_stream(&mut self, length: Option<u64>) -> io::Result<ResponseStream<'_>> {
+        if self.completed {
+            panic!("Cannot stream response after response has been sent");
+        }
+        self.completed = true;
+        self.streamed = true;
+        let Some(connection) = self.connection.as_mut() else {
+            return Err(io::Error::new(
+                ErrorKind::NotConnected,
+                "Context has no connection to stream to",
+            ));
+        };
+        if length.is_some() {
+            connection.chunked = false;
+        } else if !connection.chunked {
+            connection.keep_alive = false;
+        }
+
+        let response = &mut self.response;
+        if connection.chunked {
+            response.set_header("Transfer-Encoding", "chunked");
+        } else {
+            response.remove_header("Transfer-Encoding");
+        }
+        response.set_header(
+            "Connection",
+            if connection.keep_alive {
+                "keep-alive"
+            } else {
+                "close"
+            },
+        );
+        connection.writer.write_all(&response.head_bytes(length))?;
+        connection.writer.flush()?;
+        Ok(ResponseStream::new(connection, length))
+    }
+
+    /** Replaces the status, headers and body of a response that is still to be sent or
+    streamed, without completing the context. */
+    pub(crate) fn set_response(&mut self, response: HttpResponse) {
+        if self.completed {
+            panic!("Cannot set response after response has been sent");
+        }
+        self.response = response;
+    }
+
+    /** Whether the response was already sent with `send_text`, `send_bytes`, `send` or `stream`. */
+    pub fn is_completed(&self) -> bool {
+        self.completed
+    }
+
+    /** Discards a response that was sent but not yet written, so another can replace it. */
+    pub(crate) fn reset_response(&mut self) {
+        self.completed = false;
+        self.response = HttpResponse::text(HttpStatusCode::Ok, "");
+    }
+
+    /** Takes the response to write once the handler and middleware are done. */
+    pub(crate) fn take_response(&mut self) -> HttpResponse {
+        std::mem::replace(&mut self.response, HttpResponse::new(HttpStatusCode::Ok))
+    }
+
+    /** Whether the response was already written through `stream`. */
+    pub fn is_streamed(&self) -> bool {
+        self.streamed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::http_method::HttpMethod;
+    use std::collections::HashMap;
+
+    fn streaming_context() -> Context {
+        let request = Request::new(
+            HttpMethod::Get,
+            "/".to_string(),
+            String::new(),
+            HashMap::new(),
+            HashMap::new(),
+            String::new(),
+        );
+        let mut context = Context::new(request, HttpStatusCode::Ok);
+        context.attach(Connection {
+            writer: Box::new(io::sink()),
+            keep_alive: true,
+            chunked: true,
+            head_only: false,
+        });
+        context
+    }
+
+    #[test]
+    fn streaming_completes_the_context() {
+        let mut context = streaming_context();
+        context.stream().unwrap().finish().unwrap();
+        assert!(context.is_completed());
+        assert!(context.is_streamed());
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot send response")]
+    fn a_streamed_response_cannot_be_sent_again() {
+        let mut context = streaming_context();
+        context.stream().unwrap().finish().unwrap();
+        context.send_text("Hello");
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot stream response")]
+    fn a_sent_response_cannot_be_streamed() {
+        let mut context = streaming_context();
+        context.send_text("Hello");
+        let _ = context.stream();
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot set header")]
+    fn headers_cannot_change_once_streamed() {
+        let mut context = streaming_context();
+        context.stream().unwrap().finish().unwrap();
+        context.set_header("X-Late", "1");
+    }
+}
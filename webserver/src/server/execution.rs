@@ -0,0 +1,92 @@
+use super::shutdown::Incoming;
+use std::{
+    net::TcpStream,
+    sync::{Arc, Mutex, mpsc},
+    thread,
+};
+
+/** Handles a single accepted connection from start to finish. */
+pub type ConnectionHandler = Arc<dyn Fn(TcpStream) + Send + Sync>;
+
+/** Decides which thread handles each connection accepted by the server. */
+pub trait ExecutionModel: Send + Sync {
+    /** Passes each connection from `incoming` to `handle`. Once `incoming` ends on shutdown,
+    returns after every connection it started has been handled. */
+    fn run(&self, incoming: &mut Incoming, handle: ConnectionHandler);
+
+    /** Whether connections may stay open between requests. A model that serves one
+    connection at a time answers false, or one idle client would hold up every other. */
+    fn keep_alive(&self) -> bool {
+        true
+    }
+}
+
+/** Handles every connection on the accepting thread, one at a time.
+Each connection is closed after its first response. */
+pub struct Sequential;
+
+impl ExecutionModel for Sequential {
+    fn run(&self, incoming: &mut Incoming, handle: ConnectionHandler) {
+        for stream in incoming {
+            handle(stream);
+        }
+    }
+
+    fn keep_alive(&self) -> bool {
+        false
+    }
+}
+
+/** Spawns a new thread for every connection. */
+pub struct ThreadPerConnection;
+
+impl ExecutionModel for ThreadPerConnection {
+    fn run(&self, incoming: &mut Incoming, handle: ConnectionHandler) {
+        let mut workers = Vec::new();
+        for stream in incoming {
+            workers.retain(|worker: &thread::JoinHandle<()>| !worker.is_finished());
+            let handle = Arc::clone(&handle);
+            workers.push(thread::spawn(move || handle(stream)));
+        }
+        for worker in workers {
+            let _ = worker.join();
+        }
+    }
+}
+
+/** Hands connections to a fixed number of worker threads through a shared channel. */
+pub struct FixedPool {
+    pub threads: u32,
+}
+
+impl ExecutionModel for FixedPool {
+    fn run(&self, incoming: &mut Incoming, handle: ConnectionHandler) {
+        let (tx, rx) = mpsc::channel::<TcpStream>();
+        let rx = Arc::new(Mutex::new(rx));
+
+        let workers: Vec<_> = (0..self.threads.max(1))
+            .map(|_| {
+                let handle = Arc::clone(&handle);
+                let rx = Arc::clone(&rx);
+                thread::spawn(move || {
+                    loop {
+                        let stream = rx.lock().unwrap().recv();
+                        let Ok(stream) = stream else {
+                            break;
+                        };
+                        handle(stream);
+                    }
+                })
+            })
+            .collect();
+
+        for stream in incoming {
+            let _ = tx.send(stream);
+        }
+        // Workers stop once the connections already queued are handled
+        drop(tx);
+        for worker in workers {
+            let _ = worker.join();
+        }
+    }
+}
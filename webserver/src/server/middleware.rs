@@ -0,0 +1,176 @@
+use super::context::Context;
+use crate::{
+    http::http_method::http_method_to_string,
+    response::internal_server_error_response::internal_server_error_response,
+};
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
+    time::Instant,
+};
+
+/** Runs the rest of the chain: the remaining layers and finally the handler. */
+pub type Next<'a> = &'a dyn Fn(&mut Context);
+
+/** A layer around handlers. It may act before and after calling `next`,
+or answer by itself by completing the context without calling it. */
+pub type Middleware = Arc<dyn Fn(&mut Context, Next<'_>) + Send + Sync>;
+
+/** A middleware and the requests it applies to. */
+pub(crate) struct Layer {
+    prefix: String,
+    middleware: Middleware,
+}
+
+impl Layer {
+    pub(crate) fn new(prefix: &str, middleware: Middleware) -> Layer {
+        Layer {
+            prefix: prefix.trim_end_matches('/').to_string(),
+            middleware,
+        }
+    }
+
+    /** Whether `path` is the prefix itself or lies under it. `/` covers every path. */
+    pub(crate) fn applies_to(&self, path: &str) -> bool {
+        match path.strip_prefix(&self.prefix) {
+            Some(rest) => rest.is_empty() || rest.starts_with('/'),
+            None => false,
+        }
+    }
+}
+
+/** Runs `layers` in order around `endpoint`. */
+pub(crate) fn run(layers: &[&Layer], context: &mut Context, endpoint: Next<'_>) {
+    match layers.split_first() {
+        Some((layer, rest)) => (layer.middleware)(context, &|context| run(rest, context, endpoint)),
+        None => endpoint(context),
+    }
+}
+
+/** Prints the method, path and status of every request. */
+pub fn logging() -> impl Fn(&mut Context, Next<'_>) + Send + Sync {
+    |context, next| {
+        next(context);
+        let request = context.get_request();
+        println!(
+            "{} {} {}",
+            http_method_to_string(request.method()),
+            request.path(),
+            context.get_status().code()
+        );
+    }
+}
+
+/** Prints how long every request took to handle. */
+pub fn timing() -> impl Fn(&mut Context, Next<'_>) + Send + Sync {
+    |context, next| {
+        let start = Instant::now();
+        next(context);
+        let request = context.get_request();
+        println!(
+            "{} {} took {:?}",
+            http_method_to_string(request.method()),
+            request.path(),
+            start.elapsed()
+        );
+    }
+}
+
+/** Answers 500 when a handler panics. The server already answers 500 to panics that
+escape every layer; this one turns them into a response early enough for the layers
+added before it, such as `logging`, to see it. */
+pub fn catch_panics() -> impl Fn(&mut Context, Next<'_>) + Send + Sync {
+    |context, next| {
+        if panic::catch_unwind(AssertUnwindSafe(|| next(context))).is_err() {
+            // A streamed response is already on the wire and cannot be replaced
+            if !context.is_streamed() {
+                context.reset_response();
+                context.send(internal_server_error_response());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        http::{http_method::HttpMethod, http_status_code::HttpStatusCode},
+        server::request::Request,
+    };
+    use std::{collections::HashMap, sync::Mutex};
+
+    fn context() -> Context {
+        let request = Request::new(
+            HttpMethod::Get,
+            "/".to_string(),
+            String::new(),
+            HashMap::new(),
+            HashMap::new(),
+            String::new(),
+        );
+        Context::new(request, HttpStatusCode::Ok)
+    }
+
+    #[test]
+    fn prefixes_match_whole_segments() {
+        let layer = Layer::new("/admin/", Arc::new(|context, next| next(context)));
+        assert!(layer.applies_to("/admin"));
+        assert!(layer.applies_to("/admin/users"));
+        assert!(!layer.applies_to("/administrator"));
+        assert!(!layer.applies_to("/"));
+
+        let root = Layer::new("/", Arc::new(|context, next| next(context)));
+        assert!(root.applies_to("/"));
+        assert!(root.applies_to("/admin"));
+    }
+
+    #[test]
+    fn layers_nest_in_the_order_given() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let recording = |name: &'static str| {
+            let calls = Arc::clone(&calls);
+            Layer::new(
+                "/",
+                Arc::new(move |context: &mut Context, next: Next<'_>| {
+                    calls.lock().unwrap().push(format!("{name} in"));
+                    next(context);
+                    calls.lock().unwrap().push(format!("{name} out"));
+                }),
+            )
+        };
+        let (outer, inner) = (recording("outer"), recording("inner"));
+        let endpoint = |_: &mut Context| calls.lock().unwrap().push("handler".to_string());
+
+        run(&[&outer, &inner], &mut context(), &endpoint);
+        assert_eq!(
+            *calls.lock().unwrap(),
+            ["outer in", "inner in", "handler", "inner out", "outer out"]
+        );
+    }
+
+    #[test]
+    fn panics_become_a_500_the_outer_layers_see() {
+        let seen = Arc::new(Mutex::new(None));
+        let observer = {
+            let seen = Arc::clone(&seen);
+            Layer::new(
+                "/",
+                Arc::new(move |context: &mut Context, next: Next<'_>| {
+                    next(context);
+                    *seen.lock().unwrap() = Some(context.get_status().code());
+                }),
+            )
+        };
+        let catching = Layer::new("/", Arc::new(catch_panics()));
+        let mut context = context();
+
+        run(&[&observer, &catching], &mut context, &|context| {
+            context.set_header("X-Partial", "1");
+            panic!("boom");
+        });
+        assert_eq!(*seen.lock().unwrap(), Some(500));
+        assert_eq!(context.get_body(), b"Internal Server Error");
+        assert_eq!(context.get_response_header("X-Partial"), None);
+    }
+}
@@ -0,0 +1,13 @@
+pub mod chunked;
+pub mod connection;
+pub mod context;
+pub mod execution;
+pub mod middleware;
+pub mod query;
+pub mod request;
+pub mod response_stream;
+pub mod router;
+pub mod shutdown;
+pub(crate) mod static_files;
+pub(crate) mod timed_stream;
+pub mod web_server;
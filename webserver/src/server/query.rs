@@ -0,0 +1,69 @@
+/** Parses a raw query string like `q=foo&max=10` into its decoded key/value pairs,
+keeping their order and any repeated keys. */
+pub fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode_component(key), decode_component(value))
+        })
+        .collect()
+}
+
+/** Decodes `%XX` escapes and `+` as a space, as used in query strings. */
+pub fn decode_component(value: &str) -> String {
+    percent_decode(value, true)
+}
+
+/** Decodes `%XX` escapes in a request path. Unlike in queries, `+` stays as is. */
+pub fn decode_path(path: &str) -> String {
+    percent_decode(path, false)
+}
+
+fn percent_decode(value: &str, plus_as_space: bool) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' if plus_as_space => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len()
+                && bytes[i + 1].is_ascii_hexdigit()
+                && bytes[i + 2].is_ascii_hexdigit() =>
+            {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap();
+                decoded.push(u8::from_str_radix(hex, 16).unwrap());
+                i += 2;
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_repeated_keys_in_order() {
+        let query = parse_query("tag=a&q=x+y&tag=b%26c&flag");
+        assert_eq!(
+            query,
+            vec![
+                ("tag".to_string(), "a".to_string()),
+                ("q".to_string(), "x y".to_string()),
+                ("tag".to_string(), "b&c".to_string()),
+                ("flag".to_string(), String::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn paths_keep_plus_signs() {
+        assert_eq!(decode_path("/files/a%20b+c"), "/files/a b+c");
+        assert_eq!(decode_path("/bad%2"), "/bad%2");
+    }
+}
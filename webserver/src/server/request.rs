@@ -0,0 +1,78 @@
+use super::query::parse_query;
+use crate::http::http_method::HttpMethod;
+use std::collections::HashMap;
+
+pub struct Request {
+    method: HttpMethod,
+    path: String,
+    raw_query: String,
+    query: Vec<(String, String)>,
+    params: HashMap<String, String>,
+    headers: HashMap<String, String>,
+    body: String,
+}
+
+impl Request {
+    pub fn new(
+        method: HttpMethod,
+        path: String,
+        raw_query: String,
+        params: HashMap<String, String>,
+        headers: HashMap<String, String>,
+        body: String,
+    ) -> Request {
+        Request {
+            method,
+            path,
+            query: parse_query(&raw_query),
+            raw_query,
+            params,
+            headers,
+            body,
+        }
+    }
+
+    /** Gets the method the request was made with. */
+    pub fn method(&self) -> &HttpMethod {
+        &self.method
+    }
+
+    /** Gets the percent-decoded path the request was routed by, without the query string. */
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /** Gets the query string exactly as sent, without the leading `?`. */
+    pub fn raw_query(&self) -> &str {
+        &self.raw_query
+    }
+
+    /** Gets the first decoded value of `key` in the query string. */
+    pub fn query(&self, key: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /** Gets every decoded value of `key` in the query string, in order. */
+    pub fn query_all(&self, key: &str) -> Vec<&str> {
+        self.query
+            .iter()
+            .filter(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    pub fn get_param(&self, key: &str) -> Option<&String> {
+        self.params.get(key)
+    }
+
+    pub fn get_header(&self, key: &str) -> Option<&String> {
+        self.headers.get(&key.to_ascii_lowercase())
+    }
+
+    pub fn get_body(&self) -> &str {
+        &self.body
+    }
+}
@@ -0,0 +1,101 @@
+use std::io::{self, ErrorKind, Write};
+
+/** The connection a handler's response is written to, for handlers that stream. */
+pub(crate) struct Connection {
+    pub writer: Box<dyn Write>,
+    pub keep_alive: bool,
+    /** Whether the body is chunked. HTTP/1.0 clients do not understand chunked responses,
+    and bodies of known length do not need it. */
+    pub chunked: bool,
+    /** Set for HEAD requests, whose body is never sent. */
+    pub head_only: bool,
+}
+
+/** Writes a response body to the client as the handler produces it.
+Every write is sent as one chunk, so small writes are better grouped with a `BufWriter`.
+The body ends when the stream is finished or dropped, unless dropped by a panic. */
+pub struct ResponseStream<'a> {
+    connection: &'a mut Connection,
+    /** For bodies sent with a `Content-Length`, the bytes still to be written. */
+    remaining: Option<u64>,
+    finished: bool,
+}
+
+impl<'a> ResponseStream<'a> {
+    pub(crate) fn new(connection: &'a mut Connection, length: Option<u64>) -> ResponseStream<'a> {
+        ResponseStream {
+            connection,
+            remaining: length,
+            finished: false,
+        }
+    }
+
+    /** Ends the body, reporting whether the client received its end. */
+    pub fn finish(mut self) -> io::Result<()> {
+        self.end()
+    }
+
+    fn end(&mut self) -> io::Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+        if let Some(remaining) = self.remaining
+            && remaining > 0
+            && !self.connection.head_only
+        {
+            // The client would take the start of the next response for the missing bytes
+            self.connection.keep_alive = false;
+            self.connection.writer.flush()?;
+            return Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "Body shorter than its Content-Length",
+            ));
+        }
+        if self.connection.chunked && !self.connection.head_only {
+            self.connection.writer.write_all(b"0\r\n\r\n")?;
+        }
+        self.connection.writer.flush()
+    }
+}
+
+impl Write for ResponseStream<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // An empty chunk would end the body
+        if buf.is_empty() || self.connection.head_only {
+            return Ok(buf.len());
+        }
+        if let Some(remaining) = &mut self.remaining {
+            if buf.len() as u64 > *remaining {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    "Body longer than its Content-Length",
+                ));
+            }
+            *remaining -= buf.len() as u64;
+        }
+        let writer = &mut self.connection.writer;
+        if self.connection.chunked {
+            write!(writer, "{:x}\r\n", buf.len())?;
+            writer.write_all(buf)?;
+            writer.write_all(b"\r\n")?;
+        } else {
+            writer.write_all(buf)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.connection.writer.flush()
+    }
+}
+
+impl Drop for ResponseStream<'_> {
+    fn drop(&mut self) {
+        // A handler that panicked mid-body must not make the truncated body look complete
+        if std::thread::panicking() {
+            return;
+        }
+        let _ = self.end();
+    }
+}
@@ -0,0 +1,379 @@
+use super::query::decode_path;
+use regex::Regex;
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+
+/** Decides whether a path segment can be captured by a constrained parameter. */
+pub type Validator = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+/** Maps route patterns to values, one trie node per path segment.
+Patterns are made of static segments (`users`), named parameters (`:id` or `{id}`) and
+an optional trailing catch-all (`*rest`) that captures the remaining path.
+Parameters may carry a constraint, as in `{n:u64}` or `{slug:[a-z-]+}`: the name of a
+primitive type, a validator registered with `constraint`, or otherwise a regular
+expression that the whole segment must match.
+When several patterns match, static segments win over parameters, constrained
+parameters over unconstrained ones, and parameters over catch-alls. */
+pub struct Router<T> {
+    root: Node<T>,
+    validators: HashMap<String, Validator>,
+}
+
+struct Param<T> {
+    name: String,
+    constraint: Option<(String, Validator)>,
+    node: Node<T>,
+}
+
+struct Node<T> {
+    statics: HashMap<String, Node<T>>,
+    params: Vec<Param<T>>,
+    catch_all: Option<(String, T)>,
+    value: Option<T>,
+}
+
+impl<T> Node<T> {
+    fn new() -> Node<T> {
+        Node {
+            statics: HashMap::new(),
+            params: Vec::new(),
+            catch_all: None,
+            value: None,
+        }
+    }
+
+    fn find<'a>(
+        &'a self,
+        segments: &[&str],
+        params: &mut Vec<(&'a str, String)>,
+        accept: &dyn Fn(&T) -> bool,
+        checked: bool,
+    ) -> Option<&'a T> {
+        match segments.split_first() {
+            Some((segment, rest)) => {
+                if let Some(child) = self.statics.get(*segment)
+                    && let Some(value) = child.find(rest, params, accept, checked)
+                {
+                    return Some(value);
+                }
+                if !segment.is_empty() {
+                    for param in &self.params {
+                        if checked
+                            && let Some((_, validator)) = &param.constraint
+                            && !validator(segment)
+                        {
+                            continue;
+                        }
+                        params.push((&param.name, segment.to_string()));
+                        if let Some(value) = param.node.find(rest, params, accept, checked) {
+                            return Some(value);
+                        }
+                        params.pop();
+                    }
+                }
+            }
+            None => {
+                if let Some(value) = &self.value
+                    && accept(value)
+                {
+                    return Some(value);
+                }
+            }
+        }
+        if let Some((name, value)) = &self.catch_all
+            && accept(value)
+        {
+            params.push((name, segments.join("/")));
+            return Some(value);
+        }
+        None
+    }
+}
+
+impl<T> Node<T> {
+    fn collect<'a>(&'a self, values: &mut Vec<&'a T>) {
+        values.extend(&self.value);
+        values.extend(self.catch_all.iter().map(|(_, value)| value));
+        for child in self.statics.values() {
+            child.collect(values);
+        }
+        for param in &self.params {
+            param.node.collect(values);
+        }
+    }
+}
+
+impl<T> Default for Router<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Router<T> {
+    pub fn new() -> Router<T> {
+        Router {
+            root: Node::new(),
+            validators: HashMap::new(),
+        }
+    }
+
+    /** Registers a validator usable as `{param:name}` in patterns added afterwards. */
+    pub fn constraint(
+        &mut self,
+        name: &str,
+        validator: impl Fn(&str) -> bool + Send + Sync + 'static,
+    ) {
+        self.validators
+            .insert(name.to_string(), Arc::new(validator));
+    }
+
+    /** Gets the value stored for `pattern`, inserting the result of `default` if there is none. */
+    pub fn get_or_insert_with(&mut self, pattern: &str, default: impl FnOnce() -> T) -> &mut T {
+        let Router { root, validators } = self;
+        let mut node = root;
+        let mut segments = split(pattern).peekable();
+        while let Some(segment) = segments.next() {
+            if let Some(name) = segment.strip_prefix('*') {
+                if segments.peek().is_some() {
+                    panic!(
+                        "Catch-all segment must be the last one in route {}",
+                        pattern
+                    );
+                }
+                if let Some((existing, _)) = &node.catch_all
+                    && existing != name
+                {
+                    panic!("Conflicting catch-all names in route {}", pattern);
+                }
+                let (_, value) = node
+                    .catch_all
+                    .get_or_insert_with(|| (name.to_string(), default()));
+                return value;
+            }
+            node = match parse_param(segment) {
+                Some((name, constraint)) => {
+                    let existing = node.params.iter().position(|param| {
+                        param.name == name
+                            && param.constraint.as_ref().map(|(source, _)| source.as_str())
+                                == constraint
+                    });
+                    let index = match existing {
+                        Some(index) => index,
+                        None => {
+                            // Constrained parameters are tried before unconstrained ones
+                            let index = match constraint {
+                                Some(_) => node
+                                    .params
+                                    .iter()
+                                    .position(|param| param.constraint.is_none())
+                                    .unwrap_or(node.params.len()),
+                                None => node.params.len(),
+                            };
+                            let constraint = constraint
+                                .map(|source| (source.to_string(), validator(validators, source)));
+                            node.params.insert(
+                                index,
+                                Param {
+                                    name: name.to_string(),
+                                    constraint,
+                                    node: Node::new(),
+                                },
+                            );
+                            index
+                        }
+                    };
+                    &mut node.params[index].node
+                }
+                None => node
+                    .statics
+                    .entry(segment.to_string())
+                    .or_insert_with(Node::new),
+            };
+        }
+        node.value.get_or_insert_with(default)
+    }
+
+    /** Stores `value` under `pattern`, replacing any previous one. */
+    pub fn insert(&mut self, pattern: &str, value: T) {
+        let mut value = Some(value);
+        let slot = self.get_or_insert_with(pattern, || value.take().unwrap());
+        if let Some(value) = value {
+            *slot = value;
+        }
+    }
+
+    /** Finds the value whose pattern matches `path`, together with the captured parameters.
+    `path` is given still percent-encoded: it is split into segments before they are decoded,
+    so an encoded `/` stays inside its segment. Only values for which `accept` returns true are
+    considered, so a lower priority pattern can still match when a better one is rejected. */
+    pub fn find(
+        &self,
+        path: &str,
+        accept: impl Fn(&T) -> bool,
+    ) -> Option<(&T, HashMap<String, String>)> {
+        self.find_with(path, &accept, true)
+    }
+
+    /** Like `find`, but lets parameters capture segments their constraints reject.
+    Useful to tell a malformed parameter apart from a path that does not exist. */
+    pub fn find_unchecked(
+        &self,
+        path: &str,
+        accept: impl Fn(&T) -> bool,
+    ) -> Option<(&T, HashMap<String, String>)> {
+        self.find_with(path, &accept, false)
+    }
+
+    /** Every value stored, in no particular order. */
+    pub fn values(&self) -> Vec<&T> {
+        let mut values = Vec::new();
+        self.root.collect(&mut values);
+        values
+    }
+
+    fn find_with(
+        &self,
+        path: &str,
+        accept: &dyn Fn(&T) -> bool,
+        checked: bool,
+    ) -> Option<(&T, HashMap<String, String>)> {
+        let segments: Vec<String> = split(path).map(decode_path).collect();
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+        let mut params = Vec::new();
+        let value = self.root.find(&segments, &mut params, accept, checked)?;
+        let params = params
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect();
+        Some((value, params))
+    }
+}
+
+/** Splits `:name`, `{name}` and `{name:constraint}` segments into name and constraint. */
+fn parse_param(segment: &str) -> Option<(&str, Option<&str>)> {
+    if let Some(name) = segment.strip_prefix(':') {
+        return Some((name, None));
+    }
+    let inner = segment.strip_prefix('{')?.strip_suffix('}')?;
+    match inner.split_once(':') {
+        Some((name, constraint)) => Some((name, Some(constraint))),
+        None => Some((inner, None)),
+    }
+}
+
+/** Resolves the constraint of a `{param:constraint}` segment. */
+fn validator(validators: &HashMap<String, Validator>, constraint: &str) -> Validator {
+    match constraint {
+        "u8" => parses::<u8>(),
+        "u16" => parses::<u16>(),
+        "u32" => parses::<u32>(),
+        "u64" => parses::<u64>(),
+        "usize" => parses::<usize>(),
+        "i8" => parses::<i8>(),
+        "i16" => parses::<i16>(),
+        "i32" => parses::<i32>(),
+        "i64" => parses::<i64>(),
+        "isize" => parses::<isize>(),
+        "f32" => parses::<f32>(),
+        "f64" => parses::<f64>(),
+        "bool" => parses::<bool>(),
+        _ => match validators.get(constraint) {
+            Some(validator) => Arc::clone(validator),
+            None => {
+                let regex = Regex::new(&format!("^(?:{})$", constraint))
+                    .unwrap_or_else(|e| panic!("Invalid route constraint {}: {}", constraint, e));
+                Arc::new(move |segment| regex.is_match(segment))
+            }
+        },
+    }
+}
+
+fn parses<T: FromStr>() -> Validator {
+    Arc::new(|segment| segment.parse::<T>().is_ok())
+}
+
+fn split(path: &str) -> std::str::Split<'_, char> {
+    path.strip_prefix('/').unwrap_or(path).split('/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn router() -> Router<&'static str> {
+        let mut router = Router::new();
+        router.insert("/", "root");
+        router.insert("/users/me", "me");
+        router.insert("/users/:id", "user");
+        router.insert("/users/:id/posts/:post_id", "post");
+        router.insert("/files/*rest", "files");
+        router
+    }
+
+    #[test]
+    fn static_segments_win_over_params() {
+        let router = router();
+        assert_eq!(router.find("/users/me", |_| true).unwrap().0, &"me");
+        let (value, params) = router.find("/users/42", |_| true).unwrap();
+        assert_eq!(value, &"user");
+        assert_eq!(params["id"], "42");
+    }
+
+    #[test]
+    fn captures_every_named_param() {
+        let router = router();
+        let (value, params) = router.find("/users/7/posts/99", |_| true).unwrap();
+        assert_eq!(value, &"post");
+        assert_eq!(params["id"], "7");
+        assert_eq!(params["post_id"], "99");
+    }
+
+    #[test]
+    fn catch_all_captures_the_rest_of_the_path() {
+        let router = router();
+        let (value, params) = router.find("/files/a/b/c.txt", |_| true).unwrap();
+        assert_eq!(value, &"files");
+        assert_eq!(params["rest"], "a/b/c.txt");
+        assert_eq!(router.find("/files", |_| true).unwrap().1["rest"], "");
+    }
+
+    #[test]
+    fn segments_are_decoded_after_splitting() {
+        let router = router();
+        let (value, params) = router.find("/users/a%2Fb", |_| true).unwrap();
+        assert_eq!(value, &"user");
+        assert_eq!(params["id"], "a/b");
+        assert_eq!(router.find("/us%65rs/m%65", |_| true).unwrap().0, &"me");
+    }
+
+    #[test]
+    fn rejected_values_fall_through_to_other_patterns() {
+        let router = router();
+        let (value, params) = router.find("/users/me", |v| *v != "me").unwrap();
+        assert_eq!(value, &"user");
+        assert_eq!(params["id"], "me");
+        assert!(router.find("/users", |_| true).is_none());
+        assert!(router.find("/users/", |_| true).is_none());
+        assert_eq!(router.find("/", |_| true).unwrap().0, &"root");
+    }
+
+    #[test]
+    fn constraints_decide_which_param_captures_a_segment() {
+        let mut router = Router::new();
+        router.constraint("even", |s| s.parse::<u64>().is_ok_and(|n| n % 2 == 0));
+        router.insert("/pi/{n:u64}", "number");
+        router.insert("/pi/{name}", "name");
+        router.insert("/even/{n:even}", "even");
+        router.insert("/slug/{slug:[a-z-]+}", "slug");
+
+        assert_eq!(router.find("/pi/10", |_| true).unwrap().0, &"number");
+        assert_eq!(router.find("/pi/ten", |_| true).unwrap().0, &"name");
+        assert_eq!(router.find("/even/4", |_| true).unwrap().0, &"even");
+        assert!(router.find("/even/3", |_| true).is_none());
+        assert_eq!(
+            router.find_unchecked("/even/3", |_| true).unwrap().0,
+            &"even"
+        );
+        assert_eq!(router.find("/slug/a-b", |_| true).unwrap().1["slug"], "a-b");
+        assert!(router.find("/slug/A1", |_| true).is_none());
+    }
+}
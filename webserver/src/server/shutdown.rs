@@ -0,0 +1,221 @@
+use std::{
+    collections::HashMap,
+    net::{self, IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        Arc, Condvar, Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{self, Receiver},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+/** How long an accept thread waits before retrying after an error, such as running out
+of file descriptors, that would otherwise make it spin. */
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(50);
+
+/** How long waking an accept thread may wait for its listener to take the connection. */
+const WAKE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/** Coordinates stopping a server: accept loops end, idle connections are closed,
+and connections with a request in flight are given time to finish it. */
+pub struct Shutdown {
+    requested: Arc<AtomicBool>,
+    connections: Mutex<HashMap<u64, Tracked>>,
+    /** Notified when shutdown is requested and whenever a connection ends. */
+    changed: Condvar,
+    next_id: AtomicU64,
+    /** The addresses accept threads are blocked on, connected to once to wake them. */
+    listening: Mutex<Vec<SocketAddr>>,
+}
+
+struct Tracked {
+    stream: TcpStream,
+    /** Whether the connection is waiting for its next request rather than serving one. */
+    idle: bool,
+}
+
+impl Shutdown {
+    pub(crate) fn new() -> Shutdown {
+        Shutdown {
+            requested: Arc::new(AtomicBool::new(false)),
+            connections: Mutex::new(HashMap::new()),
+            changed: Condvar::new(),
+            next_id: AtomicU64::new(0),
+            listening: Mutex::new(Vec::new()),
+        }
+    }
+
+    /** The flag that requests shutdown when set, for signal handlers to set. */
+    pub(crate) fn flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.requested)
+    }
+
+    /** Whether the server has been asked to stop. */
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    /** Asks the server to stop, closing the connections that are waiting for a request
+    and waking the threads blocked accepting new ones. */
+    pub(crate) fn request(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+        {
+            let connections = self.connections.lock().unwrap();
+            for tracked in connections.values().filter(|tracked| tracked.idle) {
+                let _ = tracked.stream.shutdown(net::Shutdown::Read);
+            }
+            self.changed.notify_all();
+        }
+        for address in self.listening.lock().unwrap().iter() {
+            let _ = TcpStream::connect_timeout(&reachable(*address), WAKE_TIMEOUT);
+        }
+    }
+
+    /** Accepts connections on every one of `listeners` from a thread of its own,
+    until shutdown is requested. */
+    pub(crate) fn incoming(self: &Arc<Self>, listeners: Vec<TcpListener>) -> Incoming {
+        let (sender, receiver) = mpsc::channel();
+        // Registered before any thread checks the flag, so a request never misses one
+        self.listening.lock().unwrap().extend(
+            listeners
+                .iter()
+                .filter_map(|listener| listener.local_addr().ok()),
+        );
+        let acceptors = listeners
+            .into_iter()
+            .map(|listener| {
+                let shutdown = Arc::clone(self);
+                let sender = sender.clone();
+                thread::spawn(move || {
+                    while !shutdown.is_requested() {
+                        let accepted = listener.accept();
+                        // The connection that woke us up is dropped with the rest
+                        if shutdown.is_requested() {
+                            break;
+                        }
+                        match accepted {
+                            Ok((stream, _)) => {
+                                if sender.send(stream).is_err() {
+                                    break;
+                                }
+                            }
+                            Err(_) => thread::sleep(ACCEPT_BACKOFF),
+                        }
+                    }
+                })
+            })
+            .collect();
+        Incoming {
+            receiver,
+            acceptors,
+        }
+    }
+
+    /** Registers a connection so shutdown can close it, until the returned tracker drops. */
+    pub(crate) fn track(&self, stream: &TcpStream) -> Option<Tracker<'_>> {
+        let stream = stream.try_clone().ok()?;
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let tracked = Tracked {
+            stream,
+            idle: false,
+        };
+        self.connections.lock().unwrap().insert(id, tracked);
+        Some(Tracker { shutdown: self, id })
+    }
+
+    /** Once shutdown is requested, waits up to `timeout` for the connections still serving
+    a request, then closes those that remain so their threads can be joined. */
+    pub(crate) fn drain(&self, timeout: Duration) {
+        let connections = self.connections.lock().unwrap();
+        let connections = self
+            .changed
+            .wait_while(connections, |_| !self.is_requested())
+            .unwrap();
+        let (connections, _) = self
+            .changed
+            .wait_timeout_while(connections, timeout, |connections| !connections.is_empty())
+            .unwrap();
+        for tracked in connections.values() {
+            let _ = tracked.stream.shutdown(net::Shutdown::Both);
+        }
+    }
+}
+
+/** An address that reaches a listener bound to `address`, which may be a wildcard. */
+fn reachable(address: SocketAddr) -> SocketAddr {
+    let ip = match address.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+        ip => ip,
+    };
+    SocketAddr::new(ip, address.port())
+}
+
+/** The connections accepted on every listener of a server. Iterating blocks until the
+next one arrives, and ends once shutdown is requested. */
+pub struct Incoming {
+    receiver: Receiver<TcpStream>,
+    acceptors: Vec<JoinHandle<()>>,
+}
+
+impl Incoming {
+    /** Waits for the accept threads, which end once shutdown is requested. */
+    pub(crate) fn join(&mut self) {
+        for acceptor in self.acceptors.drain(..) {
+            let _ = acceptor.join();
+        }
+    }
+}
+
+impl Iterator for Incoming {
+    type Item = TcpStream;
+
+    fn next(&mut self) -> Option<TcpStream> {
+        self.receiver.recv().ok()
+    }
+}
+
+/** Keeps a connection registered with `Shutdown` while it is being served. */
+pub(crate) struct Tracker<'a> {
+    shutdown: &'a Shutdown,
+    id: u64,
+}
+
+impl Tracker<'_> {
+    /** Marks the connection as waiting for its next request, or as serving one.
+    Returns false when a shutdown was requested and the connection should close instead. */
+    pub(crate) fn set_idle(&self, idle: bool) -> bool {
+        let mut connections = self.shutdown.connections.lock().unwrap();
+        if let Some(tracked) = connections.get_mut(&self.id) {
+            tracked.idle = idle;
+        }
+        !self.shutdown.is_requested()
+    }
+}
+
+impl Drop for Tracker<'_> {
+    fn drop(&mut self) {
+        let mut connections = self.shutdown.connections.lock().unwrap();
+        connections.remove(&self.id);
+        self.shutdown.changed.notify_all();
+    }
+}
+
+/** Stops a running `WebServer` from any thread: it stops accepting connections, lets
+requests in flight finish, and `serve` returns once its threads are done. */
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    shutdown: Arc<Shutdown>,
+}
+
+impl ShutdownHandle {
+    pub(crate) fn new(shutdown: Arc<Shutdown>) -> ShutdownHandle {
+        ShutdownHandle { shutdown }
+    }
+
+    /** Asks the server to stop. Returns right away; `serve` returns once drained. */
+    pub fn shutdown(&self) {
+        self.shutdown.request();
+    }
+}
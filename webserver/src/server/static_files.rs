@@ -0,0 +1,397 @@
+use super::context::Context;
+use crate::{
+    http::{http_method::HttpMethod, http_status_code::HttpStatusCode},
+    response::{http_response::HttpResponse, not_found_response::not_found_response},
+};
+use std::{
+    fs::File,
+    io::{self, ErrorKind, Read, Seek, SeekFrom, Write},
+    ops::Range,
+    path::{Component, Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/** Ranges a single request may ask for once overlapping ones are merged. Requests for more
+are answered with the whole file, so many tiny ranges cannot multiply the work. */
+const MAX_RANGES: usize = 16;
+
+/** Serves the file at `relative` under `root`, or `index.html` for directories.
+Answers 404 for paths that try to leave `root`. */
+pub(crate) fn serve_file(context: &mut Context, root: &Path, relative: &str) {
+    let Some(path) = resolve(root, relative) else {
+        context.send(not_found_response());
+        return;
+    };
+    // Once the body streams, an error can only cut it short, which closes the connection
+    if send_file(context, &path).is_err() && !context.is_completed() {
+        context.send(not_found_response());
+    }
+}
+
+/** Maps a request path onto a file under `root`, refusing anything that would escape it,
+whether through `..` segments, absolute paths or symbolic links. */
+fn resolve(root: &Path, relative: &str) -> Option<PathBuf> {
+    let mut path = root.to_path_buf();
+    for component in Path::new(relative).components() {
+        match component {
+            Component::Normal(segment) => path.push(segment),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    let root = root.canonicalize().ok()?;
+    let mut path = path.canonicalize().ok()?;
+    if path.is_dir() {
+        path = path.join("index.html").canonicalize().ok()?;
+    }
+    (path.starts_with(&root) && path.is_file()).then_some(path)
+}
+
+fn send_file(context: &mut Context, path: &Path) -> io::Result<()> {
+    let mut file = File::open(path)?;
+    let metadata = file.metadata()?;
+    let length = metadata.len();
+    let modified = metadata.modified()?;
+    let since_epoch = modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let etag = format!("\"{:x}-{:x}\"", length, since_epoch.as_nanos());
+    let last_modified = httpdate::fmt_http_date(modified);
+    let content_type = mime_type(path);
+
+    let base = HttpResponse::new(HttpStatusCode::Ok)
+        .with_header("Cache-Control", "no-cache")
+        .with_header("ETag", &etag)
+        .with_header("Last-Modified", &last_modified)
+        .with_header("Accept-Ranges", "bytes");
+
+    let request = context.get_request();
+    if not_modified(
+        request.get_header("If-None-Match").map(String::as_str),
+        request.get_header("If-Modified-Since").map(String::as_str),
+        &etag,
+        modified,
+    ) {
+        let mut response = base;
+        response.status = HttpStatusCode::NotModified;
+        context.send(response);
+        return Ok(());
+    }
+
+    // A Range is only honored if the client's copy, named by If-Range, is still current
+    let range = request.get_header("Range").filter(|_| {
+        request
+            .get_header("If-Range")
+            .is_none_or(|validator| validator == &etag || validator == &last_modified)
+    });
+    let ranges = match range.map(|range| parse_ranges(range, length)) {
+        None | Some(Ranges::Ignored) => None,
+        Some(Ranges::Unsatisfiable) => {
+            let mut response = base.with_header("Content-Range", &format!("bytes */{}", length));
+            response.status = HttpStatusCode::RangeNotSatisfiable;
+            context.send(response);
+            return Ok(());
+        }
+        Some(Ranges::Satisfiable(ranges)) => Some(ranges),
+    };
+
+    let (mut response, body) = match ranges.as_deref() {
+        None => (
+            base.with_header("Content-Type", content_type),
+            FileBody::single(0..length),
+        ),
+        Some([range]) => (
+            base.with_header("Content-Type", content_type)
+                .with_header("Content-Range", &content_range(range, length)),
+            FileBody::single(range.clone()),
+        ),
+        Some(ranges) => {
+            let boundary = format!("{:016x}", since_epoch.as_nanos() as u64 ^ length);
+            let content_type = format!("multipart/byteranges; boundary={}", boundary);
+            let body = FileBody::multipart(ranges, length, &boundary, mime_type(path));
+            (base.with_header("Content-Type", &content_type), body)
+        }
+    };
+    if ranges.is_some() {
+        response.status = HttpStatusCode::PartialContent;
+    }
+    let head_only = *request.method() == HttpMethod::Head;
+    context.set_response(response);
+    let mut stream = context.stream_sized(body.length())?;
+    if !head_only {
+        body.write(&mut file, &mut stream)?;
+    }
+    stream.finish()
+}
+
+/** The parts of a file a response sends, each after some bytes of its own, as the
+headers of a `multipart/byteranges` part, and followed by a closing `trailer`. */
+struct FileBody {
+    parts: Vec<(String, Range<u64>)>,
+    trailer: String,
+}
+
+impl FileBody {
+    fn single(range: Range<u64>) -> FileBody {
+        FileBody {
+            parts: vec![(String::new(), range)],
+            trailer: String::new(),
+        }
+    }
+
+    fn multipart(
+        ranges: &[Range<u64>],
+        length: u64,
+        boundary: &str,
+        content_type: &str,
+    ) -> FileBody {
+        let parts = ranges
+            .iter()
+            .enumerate()
+            .map(|(i, range)| {
+                let headers = format!(
+                    "{}--{}\r\nContent-Type: {}\r\nContent-Range: {}\r\n\r\n",
+                    if i == 0 { "" } else { "\r\n" },
+                    boundary,
+                    content_type,
+                    content_range(range, length)
+                );
+                (headers, range.clone())
+            })
+            .collect();
+        FileBody {
+            parts,
+            trailer: format!("\r\n--{}--\r\n", boundary),
+        }
+    }
+
+    /** The length of the body, known before any of it is read. */
+    fn length(&self) -> u64 {
+        let parts: u64 = self
+            .parts
+            .iter()
+            .map(|(headers, range)| headers.len() as u64 + range.end - range.start)
+            .sum();
+        parts + self.trailer.len() as u64
+    }
+
+    /** Copies the body from `file` without holding more than a buffer of it in memory. */
+    fn write(&self, file: &mut File, writer: &mut impl Write) -> io::Result<()> {
+        for (headers, range) in &self.parts {
+            writer.write_all(headers.as_bytes())?;
+            file.seek(SeekFrom::Start(range.start))?;
+            let copied = io::copy(&mut (&mut *file).take(range.end - range.start), writer)?;
+            if copied < range.end - range.start {
+                return Err(io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "File shrank while it was sent",
+                ));
+            }
+        }
+        writer.write_all(self.trailer.as_bytes())
+    }
+}
+
+/** Whether the client's cached copy is still current. If-None-Match takes precedence
+over If-Modified-Since, as in RFC 9110. */
+fn not_modified(
+    if_none_match: Option<&str>,
+    if_modified_since: Option<&str>,
+    etag: &str,
+    modified: SystemTime,
+) -> bool {
+    if let Some(tags) = if_none_match {
+        return tags.split(',').map(str::trim).any(|tag| {
+            // Weak comparison: W/"x" matches "x"
+            tag == "*" || tag.trim_start_matches("W/") == etag
+        });
+    }
+    let Some(since) = if_modified_since.and_then(|date| httpdate::parse_http_date(date).ok())
+    else {
+        return false;
+    };
+    // HTTP dates have one second resolution
+    let modified = modified
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let since = since
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    modified <= since
+}
+
+#[derive(Debug, PartialEq)]
+enum Ranges {
+    /** The header is malformed or uses another unit, so the whole file is sent. */
+    Ignored,
+    Unsatisfiable,
+    Satisfiable(Vec<Range<u64>>),
+}
+
+/** Parses a `Range` header like `bytes=0-99, 200-, -50` against a file of `length` bytes.
+Overlapping and adjacent ranges are merged, as RFC 9110 allows, so the same bytes are
+never sent twice; too many ranges are ignored. */
+fn parse_ranges(header: &str, length: u64) -> Ranges {
+    let Some(specs) = header.trim().strip_prefix("bytes=") else {
+        return Ranges::Ignored;
+    };
+    let mut ranges = Vec::new();
+    for spec in specs
+        .split(',')
+        .map(str::trim)
+        .filter(|spec| !spec.is_empty())
+    {
+        let Some((start, end)) = spec.split_once('-') else {
+            return Ranges::Ignored;
+        };
+        let range = match (start.parse::<u64>(), end.parse::<u64>()) {
+            // A suffix: the last `end` bytes
+            _ if start.is_empty() => match end.parse::<u64>() {
+                Ok(suffix) => length.saturating_sub(suffix)..length,
+                Err(_) => return Ranges::Ignored,
+            },
+            (Ok(start), _) if end.is_empty() => start..length,
+            (Ok(start), Ok(end)) if start <= end => start..end.saturating_add(1).min(length),
+            _ => return Ranges::Ignored,
+        };
+        if range.start < range.end {
+            ranges.push(range);
+        }
+    }
+    if ranges.is_empty() {
+        return Ranges::Unsatisfiable;
+    }
+    ranges.sort_by_key(|range| range.start);
+    let mut merged: Vec<Range<u64>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    if merged.len() > MAX_RANGES {
+        return Ranges::Ignored;
+    }
+    Ranges::Satisfiable(merged)
+}
+
+fn content_range(range: &Range<u64>, length: u64) -> String {
+    format!("bytes {}-{}/{}", range.start, range.end - 1, length)
+}
+
+/** Guesses the Content-Type from the file extension. */
+fn mime_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" | "map" => "application/json",
+        "txt" | "log" => "text/plain; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "md" => "text/markdown; charset=utf-8",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "pdf" => "application/pdf",
+        "wasm" => "application/wasm",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "mp4" => "video/mp4",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ranges() {
+        assert_eq!(
+            parse_ranges("bytes=0-9, 20-29, -5", 100),
+            Ranges::Satisfiable(vec![0..10, 20..30, 95..100])
+        );
+        let range = Range {
+            start: 90,
+            end: 100,
+        };
+        assert_eq!(
+            parse_ranges("bytes=90-200", 100),
+            Ranges::Satisfiable(vec![range])
+        );
+        assert_eq!(parse_ranges("bytes=100-", 100), Ranges::Unsatisfiable);
+        assert_eq!(parse_ranges("bytes=5-1", 100), Ranges::Ignored);
+        assert_eq!(parse_ranges("lines=1-2", 100), Ranges::Ignored);
+    }
+
+    #[test]
+    fn merges_overlapping_ranges_and_ignores_too_many() {
+        assert_eq!(
+            parse_ranges("bytes=50-59, 0-9, 5-19, 20-29, 55-", 100),
+            Ranges::Satisfiable(vec![0..30, 50..100])
+        );
+        let whole = Range { start: 0, end: 100 };
+        assert_eq!(
+            parse_ranges(&format!("bytes={}", vec!["0-"; 1000].join(",")), 100),
+            Ranges::Satisfiable(vec![whole])
+        );
+        let spread: Vec<String> = (0..=MAX_RANGES)
+            .map(|i| format!("{0}-{0}", i * 2))
+            .collect();
+        assert_eq!(
+            parse_ranges(&format!("bytes={}", spread.join(",")), 100),
+            Ranges::Ignored
+        );
+    }
+
+    #[test]
+    fn multipart_bodies_are_as_long_as_announced() {
+        let root = std::env::temp_dir().join(format!("webserver-ranges-{}", std::process::id()));
+        std::fs::write(&root, "0123456789").unwrap();
+        let body = FileBody::multipart(&[0..2, 5..10], 10, "b", "text/plain");
+
+        let mut written = Vec::new();
+        body.write(&mut File::open(&root).unwrap(), &mut written)
+            .unwrap();
+        assert_eq!(written.len() as u64, body.length());
+        let written = String::from_utf8(written).unwrap();
+        assert!(written.starts_with("--b\r\nContent-Type: text/plain\r\n"));
+        assert!(written.contains("Content-Range: bytes 0-1/10\r\n\r\n01\r\n--b\r\n"));
+        assert!(written.ends_with("Content-Range: bytes 5-9/10\r\n\r\n56789\r\n--b--\r\n"));
+        std::fs::remove_file(root).unwrap();
+    }
+
+    #[test]
+    fn refuses_paths_outside_the_root() {
+        let root = std::env::temp_dir().join(format!("webserver-static-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("public")).unwrap();
+        std::fs::write(root.join("public/index.html"), "index").unwrap();
+        std::fs::write(root.join("secret.txt"), "secret").unwrap();
+        std::fs::create_dir_all(root.join("public/linked")).unwrap();
+        std::os::unix::fs::symlink("../../secret.txt", root.join("public/linked/index.html"))
+            .unwrap();
+        let public = root.join("public");
+
+        assert_eq!(
+            resolve(&public, "").unwrap(),
+            public.join("index.html").canonicalize().unwrap()
+        );
+        assert!(resolve(&public, "../secret.txt").is_none());
+        assert!(resolve(&public, "/etc/passwd").is_none());
+        assert!(resolve(&public, "missing.txt").is_none());
+        // The index of a directory inside the root may itself link outside of it
+        assert!(resolve(&public, "linked").is_none());
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
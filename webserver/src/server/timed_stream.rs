@@ -0,0 +1,141 @@
+use std::{
+    io::{self, ErrorKind, Read},
+    net::TcpStream,
+    time::{Duration, Instant},
+};
+
+/** How long a phase may run before its minimum transfer rate is enforced. */
+const RATE_GRACE: Duration = Duration::from_secs(2);
+
+/** Reads from a connection against a deadline, so a client that sends nothing,
+or trickles bytes to hold a worker (a "slowloris"), is cut off.
+Each phase of a request gets its own deadline, and while a phase runs the client must
+also keep up a minimum transfer rate. Reads past either limit fail with `TimedOut`. */
+pub(crate) struct TimedStream {
+    stream: TcpStream,
+    /** Bytes per second a client must keep up, or 0 for no minimum. */
+    min_rate: u64,
+    started: Instant,
+    /** None when the phase has no time limit. */
+    deadline: Option<Instant>,
+    received: u64,
+    /** While waiting for a request, the timeout of the phase its first byte starts. */
+    pending: Option<Duration>,
+}
+
+impl TimedStream {
+    pub(crate) fn new(stream: TcpStream, min_rate: u64) -> TimedStream {
+        let now = Instant::now();
+        TimedStream {
+            stream,
+            min_rate,
+            started: now,
+            deadline: Some(now),
+            received: 0,
+            pending: None,
+        }
+    }
+
+    /** Waits up to `idle` for the next request. Once its first byte arrives, the request
+    has `timeout` to arrive in full, at the minimum rate. */
+    pub(crate) fn await_request(&mut self, idle: Duration, timeout: Duration) {
+        self.start(idle);
+        self.pending = Some(timeout);
+    }
+
+    /** Starts a phase that must complete within `timeout`, at the minimum rate.
+    A zero `timeout`, or one too long to represent, leaves the phase without a deadline. */
+    pub(crate) fn start(&mut self, timeout: Duration) {
+        self.started = Instant::now();
+        self.deadline = if timeout.is_zero() {
+            None
+        } else {
+            self.started.checked_add(timeout)
+        };
+        self.received = 0;
+        self.pending = None;
+    }
+
+    /** Counts bytes that were read ahead into a buffer towards the current phase's rate. */
+    pub(crate) fn credit(&mut self, bytes: usize) {
+        self.received += bytes as u64;
+    }
+
+    /** When the current read must give up, whichever of the phase deadline and
+    the minimum rate comes first, or None when neither applies. Idle waits are not held
+    to the rate. */
+    fn read_deadline(&self) -> Option<Instant> {
+        if self.pending.is_some() || self.min_rate == 0 {
+            return self.deadline;
+        }
+        let earned = Duration::from_secs_f64(self.received as f64 / self.min_rate as f64);
+        let rate = self.started.checked_add(RATE_GRACE.saturating_add(earned));
+        self.deadline.into_iter().chain(rate).min()
+    }
+}
+
+impl Read for TimedStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let now = Instant::now();
+        let timeout = match self.read_deadline() {
+            Some(deadline) if now >= deadline => {
+                return Err(io::Error::new(
+                    ErrorKind::TimedOut,
+                    "Read deadline exceeded",
+                ));
+            }
+            Some(deadline) => Some(deadline - now),
+            None => None,
+        };
+        self.stream.set_read_timeout(timeout)?;
+        let read = self.stream.read(buf)?;
+        if read > 0
+            && let Some(timeout) = self.pending
+        {
+            self.start(timeout);
+        }
+        self.received += read as u64;
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{io::Write, net::TcpListener};
+
+    fn connected() -> (TcpStream, TimedStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        (client, TimedStream::new(server, 0))
+    }
+
+    #[test]
+    fn the_deadline_starts_with_the_first_byte() {
+        let (mut client, mut stream) = connected();
+        stream.await_request(Duration::from_secs(5), Duration::from_millis(100));
+        client.write_all(b"GET / HTTP/1.1\r\n").unwrap();
+
+        let mut buffer = [0; 64];
+        assert_eq!(stream.read(&mut buffer).unwrap(), 16);
+        let started = Instant::now();
+        let error = stream.read(&mut buffer).unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::TimedOut | ErrorKind::WouldBlock
+        ));
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn zero_and_unrepresentable_timeouts_leave_no_deadline() {
+        let (_client, mut stream) = connected();
+        stream.start(Duration::MAX);
+        assert_eq!(stream.read_deadline(), None);
+        stream.start(Duration::ZERO);
+        assert_eq!(stream.read_deadline(), None);
+        stream.await_request(Duration::from_secs(5), Duration::MAX);
+        assert!(stream.read_deadline().is_some());
+    }
+}
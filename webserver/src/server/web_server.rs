@@ -0,0 +1,930 @@
+use crate::{
+    http::http_method::{HTTP_METHODS, HttpMethod, http_method_to_string},
+    http::http_status_code::HttpStatusCode,
+    response::{
+        bad_request_response::bad_request_response, http_response::HttpResponse,
+        internal_server_error_response::internal_server_error_response,
+        method_not_allowed_response::method_not_allowed_response,
+        not_found_response::not_found_response, options_response::options_response,
+    },
+    server::{
+        connection::{Limits, RawRequest, read_body, read_head},
+        execution::{ExecutionModel, FixedPool},
+        middleware::{self, Layer, Next},
+        query::decode_path,
+        request::Request,
+        response_stream::Connection,
+        router::Router,
+        shutdown::{Shutdown, ShutdownHandle},
+        static_files::serve_file,
+        timed_stream::TimedStream,
+    },
+};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    io::{self, BufReader, ErrorKind, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+use super::context::Context;
+use signal_hook::{
+    consts::{SIGINT, SIGTERM},
+    flag,
+    iterator::Signals,
+};
+
+type Handler = Box<dyn Fn(&mut Context) + Send + Sync>;
+type Handlers = Router<HashMap<HttpMethod, Handler>>;
+
+pub struct WebServer {
+    handlers: Handlers,
+    layers: Vec<Layer>,
+    execution: Box<dyn ExecutionModel>,
+    limits: Limits,
+    listeners: Vec<TcpListener>,
+    shutdown: Arc<Shutdown>,
+    shutdown_timeout: Duration,
+    handle_signals: bool,
+}
+
+impl Default for WebServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WebServer {
+    pub fn new() -> WebServer {
+        WebServer {
+            handlers: Router::new(),
+            layers: Vec::new(),
+            execution: Box::new(FixedPool { threads: 10 }),
+            limits: Limits::default(),
+            listeners: Vec::new(),
+            shutdown: Arc::new(Shutdown::new()),
+            shutdown_timeout: Duration::from_secs(30),
+            handle_signals: false,
+        }
+    }
+
+    /** Sets how connections are spread over threads. Defaults to a pool of 10 threads. */
+    pub fn execution(&mut self, execution: impl ExecutionModel + 'static) {
+        self.execution = Box::new(execution);
+    }
+
+    /** Uses a fixed pool of `threads` worker threads. */
+    pub fn threads(&mut self, threads: u32) {
+        self.execution(FixedPool { threads });
+    }
+
+    /** Registers a validator usable as `{param:name}` in routes added afterwards. */
+    pub fn constraint(
+        &mut self,
+        name: &str,
+        validator: impl Fn(&str) -> bool + Send + Sync + 'static,
+    ) {
+        self.handlers.constraint(name, validator);
+    }
+
+    /** Adds a route for `method` to the server, replacing any previous handler for both. */
+    pub fn route(
+        &mut self,
+        method: HttpMethod,
+        route: &str,
+        handler: impl Fn(&mut Context) + Send + Sync + 'static,
+    ) {
+        self.handlers
+            .get_or_insert_with(route, HashMap::new)
+            .insert(method, Box::new(handler));
+    }
+
+    /** Adds a get route to the server. HEAD requests are answered by it too, without the body. */
+    pub fn get(&mut self, route: &str, handler: impl Fn(&mut Context) + Send + Sync + 'static) {
+        self.route(HttpMethod::Get, route, handler);
+    }
+
+    /** Adds a post route to the server */
+    pub fn post(&mut self, route: &str, handler: impl Fn(&mut Context) + Send + Sync + 'static) {
+        self.route(HttpMethod::Post, route, handler);
+    }
+
+    /** Adds a put route to the server */
+    pub fn put(&mut self, route: &str, handler: impl Fn(&mut Context) + Send + Sync + 'static) {
+        self.route(HttpMethod::Put, route, handler);
+    }
+
+    /** Adds a delete route to the server */
+    pub fn delete(&mut self, route: &str, handler: impl Fn(&mut Context) + Send + Sync + 'static) {
+        self.route(HttpMethod::Delete, route, handler);
+    }
+
+    /** Adds a patch route to the server */
+    pub fn patch(&mut self, route: &str, handler: impl Fn(&mut Context) + Send + Sync + 'static) {
+        self.route(HttpMethod::Patch, route, handler);
+    }
+
+    /** Serves the files under `dir` at `prefix`, as in `static_dir("/assets", "./public")`.
+    Directories are served by their `index.html`. Responses carry ETag and Last-Modified
+    for conditional requests, and honor Range requests. */
+    pub fn static_dir(&mut self, prefix: &str, dir: impl Into<PathBuf>) {
+        let dir = dir.into();
+        let route = format!("{}/*path", prefix.trim_end_matches('/'));
+        self.get(&route, move |context| {
+            let path = context
+                .get_request()
+                .get_param("path")
+                .cloned()
+                .unwrap_or_default();
+            serve_file(context, &dir, &path);
+        });
+    }
+
+    /** Wraps every route under `prefix` in `middleware`, which decides whether and when the
+    handler runs. Use `/` as prefix for every route; layers run in the order they were added. */
+    pub fn around(
+        &mut self,
+        prefix: &str,
+        middleware: impl Fn(&mut Context, Next<'_>) + Send + Sync + 'static,
+    ) {
+        self.layers.push(Layer::new(prefix, Arc::new(middleware)));
+    }
+
+    /** Runs `middleware` before every handler under `prefix`.
+    Completing the context from it answers the request without running the handler. */
+    pub fn before(
+        &mut self,
+        prefix: &str,
+        middleware: impl Fn(&mut Context) + Send + Sync + 'static,
+    ) {
+        self.around(prefix, move |context, next| {
+            middleware(context);
+            if !context.is_completed() {
+                next(context);
+            }
+        });
+    }
+
+    /** Runs `middleware` after every handler under `prefix`. */
+    pub fn after(
+        &mut self,
+        prefix: &str,
+        middleware: impl Fn(&mut Context) + Send + Sync + 'static,
+    ) {
+        self.around(prefix, move |context, next| {
+            next(context);
+            middleware(context);
+        });
+    }
+
+    /** Closes each connection after it has answered `max_requests` requests. */
+    pub fn max_requests(&mut self, max_requests: usize) {
+        self.limits.max_requests = max_requests.max(1);
+    }
+
+    /** Closes kept-alive connections that wait longer than `timeout` for their next request.
+    A zero `timeout` disables the limit. */
+    pub fn idle_timeout(&mut self, timeout: Duration) {
+        self.limits.idle_timeout = timeout;
+    }
+
+    /** Answers 408 Request Timeout when a request line and its headers take longer than
+    `timeout` to arrive, counted from their first byte. A zero `timeout` disables the limit. */
+    pub fn header_timeout(&mut self, timeout: Duration) {
+        self.limits.header_timeout = timeout;
+    }
+
+    /** Answers 408 Request Timeout when a request body takes longer than `timeout` to arrive.
+    A zero `timeout` disables the limit. */
+    pub fn body_timeout(&mut self, timeout: Duration) {
+        self.limits.body_timeout = timeout;
+    }
+
+    /** Drops connections whose client does not read the response for longer than `timeout`.
+    A zero `timeout` disables the limit. */
+    pub fn write_timeout(&mut self, timeout: Duration) {
+        self.limits.write_timeout = timeout;
+    }
+
+    /** Answers 408 Request Timeout to clients that send their request slower than
+    `bytes_per_second`, after a short grace period. 0 disables the minimum. */
+    pub fn min_transfer_rate(&mut self, bytes_per_second: u64) {
+        self.limits.min_transfer_rate = bytes_per_second;
+    }
+
+    /** Answers requests whose request line is longer than `bytes` with 414 URI Too Long. */
+    pub fn max_request_line(&mut self, bytes: usize) {
+        self.limits.max_request_line = bytes;
+    }
+
+    /** Answers requests with more than `count` header lines, or more than `bytes` of them,
+    with 431 Request Header Fields Too Large. */
+    pub fn max_headers(&mut self, count: usize, bytes: usize) {
+        self.limits.max_headers = count;
+        self.limits.max_header_size = bytes;
+    }
+
+    /** Answers requests whose body is longer than `bytes` with 413 Content Too Large. */
+    pub fn max_body_size(&mut self, bytes: usize) {
+        self.limits.max_body_size = bytes;
+    }
+
+    /** Returns a handle that stops the server once it is serving. */
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle::new(Arc::clone(&self.shutdown))
+    }
+
+    /** Gives requests in flight up to `timeout` to finish once shutdown is requested,
+    after which their connections are closed. */
+    pub fn shutdown_timeout(&mut self, timeout: Duration) {
+        self.shutdown_timeout = timeout;
+    }
+
+    /** Shuts the server down gracefully on SIGINT or SIGTERM.
+    A second signal during the shutdown exits the process right away. */
+    pub fn handle_signals(&mut self) {
+        self.handle_signals = true;
+    }
+
+    /** Listens on `address`, such as `"0.0.0.0:8080"`, `"[::1]:8080"` or `("localhost", 0)`,
+    and returns the address actually bound. Port 0 binds a free port chosen by the system.
+    Can be called several times to serve the same routes on several addresses. */
+    pub fn bind(&mut self, address: impl ToSocketAddrs) -> io::Result<SocketAddr> {
+        let listener = TcpListener::bind(address)?;
+        let address = listener.local_addr()?;
+        self.listeners.push(listener);
+        Ok(address)
+    }
+
+    /** The addresses bound so far with `bind`. */
+    pub fn local_addrs(&self) -> Vec<SocketAddr> {
+        self.listeners
+            .iter()
+            .filter_map(|listener| listener.local_addr().ok())
+            .collect()
+    }
+
+    /** Listens on `address`, along with any address bound before, and serves requests
+    as `run` does. */
+    pub fn serve(mut self, address: impl ToSocketAddrs) -> io::Result<()> {
+        self.bind(address)?;
+        self.run()
+    }
+
+    /** Serves requests on the addresses bound with `bind` until shutdown is requested,
+    then returns once the requests in flight are done and every thread started by the
+    server has finished. */
+    pub fn run(self) -> io::Result<()> {
+        if self.listeners.is_empty() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "No address to listen on, bind one first",
+            ));
+        }
+        let addresses = self.local_addrs();
+        let WebServer {
+            handlers,
+            layers,
+            execution,
+            mut limits,
+            listeners,
+            shutdown,
+            shutdown_timeout,
+            handle_signals,
+        } = self;
+        if !execution.keep_alive() {
+            limits.max_requests = 1;
+        }
+        let signals = if handle_signals {
+            for signal in [SIGINT, SIGTERM] {
+                // Registered first, so it only exits when the flag is already set
+                flag::register_conditional_shutdown(signal, 1, shutdown.flag())?;
+            }
+            // A signal handler can only set a flag, so a thread does the rest of the work
+            let mut signals = Signals::new([SIGINT, SIGTERM])?;
+            let handle = signals.handle();
+            let shutdown = Arc::clone(&shutdown);
+            let watcher = thread::spawn(move || {
+                if signals.forever().next().is_some() {
+                    shutdown.request();
+                }
+            });
+            Some((handle, watcher))
+        } else {
+            None
+        };
+        for address in &addresses {
+            println!("Server is listening on {}", address);
+        }
+
+        let drain = {
+            let shutdown = Arc::clone(&shutdown);
+            thread::spawn(move || shutdown.drain(shutdown_timeout))
+        };
+        let handlers = Arc::new(handlers);
+        let layers = Arc::new(layers);
+        let handler_shutdown = Arc::clone(&shutdown);
+        let mut incoming = shutdown.incoming(listeners);
+        execution.run(
+            &mut incoming,
+            Arc::new(move |stream| {
+                // Handler panics are answered in `respond`; this keeps any other panic from
+                // taking the thread, and with Sequential the whole server, down with it
+                let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+                    handle_connection(&handlers, &layers, &limits, &handler_shutdown, stream)
+                }));
+            }),
+        );
+        // Execution models only return once shutdown is requested, but make sure
+        shutdown.request();
+        incoming.join();
+        if let Some((handle, watcher)) = signals {
+            handle.close();
+            let _ = watcher.join();
+        }
+        let _ = drain.join();
+        println!("Server has shut down");
+        Ok(())
+    }
+}
+
+/** Answers requests on `stream` until the client or the limits close it. */
+fn handle_connection(
+    handlers: &Handlers,
+    layers: &[Layer],
+    limits: &Limits,
+    shutdown: &Shutdown,
+    mut stream: TcpStream,
+) {
+    let Some(tracker) = shutdown.track(&stream) else {
+        return;
+    };
+    if stream
+        .set_write_timeout(Some(limits.write_timeout).filter(|timeout| !timeout.is_zero()))
+        .is_err()
+    {
+        return;
+    }
+    let Ok(read_half) = stream.try_clone() else {
+        return;
+    };
+    let mut reader = BufReader::new(TimedStream::new(read_half, limits.min_transfer_rate));
+
+    for served in 1.. {
+        if !tracker.set_idle(true) {
+            break;
+        }
+        reader
+            .get_mut()
+            .await_request(limits.idle_timeout, limits.header_timeout);
+        let request = read_head(&mut reader, limits).and_then(|request| {
+            let Some(mut request) = request else {
+                return Ok(None);
+            };
+            tracker.set_idle(false);
+            let buffered = reader.buffer().len();
+            reader.get_mut().start(limits.body_timeout);
+            reader.get_mut().credit(buffered);
+            request.body = read_body(&mut reader, request.method, &mut request.headers, limits)?;
+            Ok(Some(request))
+        });
+        let request = match request {
+            Ok(Some(request)) => request,
+            Ok(None) => break,
+            Err(error) => {
+                if let Some(mut response) = error.response() {
+                    response.set_header("Connection", "close");
+                    let _ = stream.write_all(&response.to_bytes(true));
+                }
+                break;
+            }
+        };
+        let method = request.method;
+        let mut keep_alive =
+            request.keep_alive() && served < limits.max_requests && !shutdown.is_requested();
+        let Ok(writer) = stream.try_clone() else {
+            break;
+        };
+        let connection = Connection {
+            writer: Box::new(writer),
+            keep_alive,
+            chunked: request.version != "HTTP/1.0",
+            head_only: method == HttpMethod::Head,
+        };
+        let Some(mut response) = respond(
+            handlers,
+            layers,
+            method,
+            request,
+            connection,
+            &mut keep_alive,
+        ) else {
+            // The handler streamed its response
+            if !keep_alive {
+                break;
+            }
+            continue;
+        };
+        // A shutdown requested while the handler ran closes the connection after it
+        keep_alive &= !shutdown.is_requested();
+        response.set_header(
+            "Connection",
+            if keep_alive { "keep-alive" } else { "close" },
+        );
+        let include_body = method != HttpMethod::Head;
+        if stream.write_all(&response.to_bytes(include_body)).is_err() || stream.flush().is_err() {
+            break;
+        }
+        if !keep_alive {
+            break;
+        }
+    }
+}
+
+/** Routes `request` to its handler and builds the response, a 500 if the handler panics.
+Returns None when the handler already streamed its response through `connection`,
+updating `keep_alive` if streaming required closing the connection. */
+fn respond(
+    handlers: &Handlers,
+    layers: &[Layer],
+    method: HttpMethod,
+    request: RawRequest,
+    connection: Connection,
+    keep_alive: &mut bool,
+) -> Option<HttpResponse> {
+    let target = request.target.as_str();
+    let (route, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let handles_method = |methods: &HashMap<HttpMethod, Handler>| {
+        methods.contains_key(&method)
+            || (method == HttpMethod::Head && methods.contains_key(&HttpMethod::Get))
+    };
+    // `OPTIONS *` asks about the server as a whole, so no route may answer it
+    let whole_server = route == "*";
+    let found = if whole_server {
+        None
+    } else {
+        handlers.find(route, handles_method)
+    };
+    // Paths without a handler still go through the middleware, answered by a fallback
+    let (params, fallback) = match &found {
+        Some((_, params)) => (params.clone(), None),
+        None => {
+            let allowed = if whole_server {
+                server_methods(handlers)
+            } else {
+                allowed_methods(handlers, route)
+            };
+            let response = if !allowed.is_empty() {
+                match method {
+                    HttpMethod::Options => options_response(&allowed),
+                    _ => method_not_allowed_response(&allowed),
+                }
+            } else if handlers.find_unchecked(route, handles_method).is_some() {
+                // A route would match if its parameter constraints were ignored
+                bad_request_response()
+            } else {
+                not_found_response()
+            };
+            (HashMap::new(), Some(response))
+        }
+    };
+
+    let body = String::from_utf8_lossy(&request.body).to_string();
+    let request = Request::new(
+        method,
+        decode_path(route),
+        query.to_string(),
+        params,
+        request.headers,
+        body,
+    );
+    let status_code = HttpStatusCode::Ok;
+    let mut context = Context::new(request, status_code);
+    context.attach(connection);
+
+    let endpoint = |context: &mut Context| match (&found, &fallback) {
+        (Some((methods, _)), _) => {
+            let handler = methods
+                .get(&method)
+                .unwrap_or_else(|| &methods[&HttpMethod::Get]);
+            handler(context);
+        }
+        (None, Some(response)) => context.send(response.clone()),
+        (None, None) => unreachable!(),
+    };
+    let path = context.get_request().path().to_string();
+    let layers: Vec<&Layer> = layers
+        .iter()
+        .filter(|layer| layer.applies_to(&path))
+        .collect();
+    let panicked = panic::catch_unwind(AssertUnwindSafe(|| {
+        middleware::run(&layers, &mut context, &endpoint)
+    }))
+    .is_err();
+
+    let connection = context.detach();
+    if context.is_streamed() {
+        // A body cut short by a panic can only be told apart by closing the connection
+        *keep_alive = !panicked && connection.is_some_and(|connection| connection.keep_alive);
+        return None;
+    }
+    if panicked {
+        return Some(internal_server_error_response());
+    }
+
+    Some(context.take_response())
+}
+
+/** Lists the methods `route` can be requested with, as in an `Allow` header.
+Empty when no route matches the path at all. */
+fn allowed_methods(handlers: &Handlers, route: &str) -> String {
+    let registered = RefCell::new(HashSet::new());
+    // Rejecting every candidate makes the router visit all the patterns matching the path
+    handlers.find(route, |methods| {
+        registered.borrow_mut().extend(methods.keys().copied());
+        false
+    });
+    let registered = registered.into_inner();
+    if registered.is_empty() {
+        return String::new();
+    }
+    allow_header(registered)
+}
+
+/** Lists every method some route of the server can be requested with, for `OPTIONS *`. */
+fn server_methods(handlers: &Handlers) -> String {
+    let registered = handlers
+        .values()
+        .into_iter()
+        .flat_map(|methods| methods.keys().copied())
+        .collect();
+    allow_header(registered)
+}
+
+/** Formats `registered` as an `Allow` header, adding the methods the server answers itself. */
+fn allow_header(mut registered: HashSet<HttpMethod>) -> String {
+    if registered.contains(&HttpMethod::Get) {
+        registered.insert(HttpMethod::Head);
+    }
+    registered.insert(HttpMethod::Options);
+    HTTP_METHODS
+        .iter()
+        .filter(|method| registered.contains(method))
+        .map(http_method_to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::execution::Sequential;
+    use std::{io::Read, thread::JoinHandle};
+
+    /** Runs `server` on an ephemeral port until the returned handle shuts it down. */
+    fn start(mut server: WebServer) -> (SocketAddr, ShutdownHandle, JoinHandle<io::Result<()>>) {
+        let address = server.bind("127.0.0.1:0").unwrap();
+        let handle = server.shutdown_handle();
+        (address, handle, thread::spawn(move || server.run()))
+    }
+
+    /** Sends `request` on a new connection and reads the response until the server closes it. */
+    fn exchange(address: SocketAddr, request: &str) -> String {
+        let mut client = TcpStream::connect(address).unwrap();
+        client.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn serves_on_an_ephemeral_port_until_shut_down() {
+        let mut server = WebServer::new();
+        server.get("/", |context| context.send_text("Hello"));
+        let address = server.bind("127.0.0.1:0").unwrap();
+        assert_ne!(address.port(), 0);
+        assert_eq!(server.local_addrs(), vec![address]);
+        let handle = server.shutdown_handle();
+        let serving = thread::spawn(move || server.run());
+
+        let response = exchange(
+            address,
+            "GET / HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n",
+        );
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with("Hello"));
+
+        handle.shutdown();
+        serving.join().unwrap().unwrap();
+        assert!(TcpStream::connect(address).is_err());
+    }
+
+    #[test]
+    fn a_panicking_handler_is_answered_with_500_and_the_server_keeps_serving() {
+        for sequential in [true, false] {
+            let mut server = WebServer::new();
+            if sequential {
+                server.execution(Sequential);
+            } else {
+                server.threads(1);
+            }
+            server.get("/boom", |_| panic!("boom"));
+            server.get("/", |context| context.send_text("Hello"));
+            let (address, handle, serving) = start(server);
+
+            let response = exchange(
+                address,
+                "GET /boom HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n",
+            );
+            assert!(response.starts_with("HTTP/1.1 500 Internal Server Error"));
+            let response = exchange(
+                address,
+                "GET / HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n",
+            );
+            assert!(response.starts_with("HTTP/1.1 200 OK"));
+
+            handle.shutdown();
+            serving.join().unwrap().unwrap();
+        }
+    }
+
+    #[test]
+    fn a_handler_panicking_mid_stream_closes_the_connection_without_ending_the_body() {
+        let mut server = WebServer::new();
+        server.get("/", |context| {
+            let mut stream = context.stream().unwrap();
+            stream.write_all(b"partial").unwrap();
+            panic!("boom");
+        });
+        let (address, handle, serving) = start(server);
+
+        // The request asks to keep the connection, so only closing it ends the read
+        let response = exchange(address, "GET / HTTP/1.1\r\nHost: a\r\n\r\n");
+        assert!(response.contains("7\r\npartial\r\n"));
+        assert!(!response.ends_with("0\r\n\r\n"));
+
+        handle.shutdown();
+        serving.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn answers_methods_without_a_handler_of_their_own() {
+        let mut server = WebServer::new();
+        server.get("/items", |context| context.send_text("list"));
+        server.post("/items", |context| context.send_text("created"));
+        let (address, handle, serving) = start(server);
+        let request = |method: &str, path: &str| {
+            exchange(
+                address,
+                &format!("{method} {path} HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n"),
+            )
+        };
+
+        let response = request("DELETE", "/items");
+        assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+        assert!(response.contains("\r\nAllow: GET, HEAD, POST, OPTIONS\r\n"));
+
+        let response = request("HEAD", "/items");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("\r\nContent-Length: 4\r\n"));
+        assert!(response.ends_with("\r\n\r\n"));
+
+        let response = request("OPTIONS", "/items");
+        assert!(response.starts_with("HTTP/1.1 204 No Content\r\n"));
+        assert!(response.contains("\r\nAllow: GET, HEAD, POST, OPTIONS\r\n"));
+
+        // Asks about the server as a whole, not about any path
+        let response = request("OPTIONS", "*");
+        assert!(response.starts_with("HTTP/1.1 204 No Content\r\n"));
+        assert!(response.contains("\r\nAllow: GET, HEAD, POST, OPTIONS\r\n"));
+
+        let response = request("DELETE", "/missing");
+        assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(!response.contains("Allow"));
+
+        handle.shutdown();
+        serving.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn sequential_servers_close_each_connection_after_one_response() {
+        let mut server = WebServer::new();
+        server.execution(Sequential);
+        server.get("/", |context| context.send_text("Hello"));
+        let (address, handle, serving) = start(server);
+
+        // Without Connection: close, only the server closing the connection ends the read
+        let response = exchange(address, "GET / HTTP/1.1\r\nHost: a\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("\r\nConnection: close\r\n"));
+
+        handle.shutdown();
+        serving.join().unwrap().unwrap();
+    }
+
+    /** A server whose only route streams its body in two writes. */
+    fn streaming_server() -> WebServer {
+        let mut server = WebServer::new();
+        server.get("/", |context| {
+            let mut stream = context.stream().unwrap();
+            stream.write_all(b"Hello, ").unwrap();
+            stream.write_all(b"world").unwrap();
+            stream.finish().unwrap();
+        });
+        server
+    }
+
+    #[test]
+    fn streamed_responses_are_chunked_for_http_1_1() {
+        let (address, handle, serving) = start(streaming_server());
+
+        let response = exchange(
+            address,
+            "GET / HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n",
+        );
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(head.contains("\r\nTransfer-Encoding: chunked"));
+        assert!(!head.contains("Content-Length"));
+        assert_eq!(body, "7\r\nHello, \r\n5\r\nworld\r\n0\r\n\r\n");
+
+        handle.shutdown();
+        serving.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn streamed_responses_end_by_closing_the_connection_for_http_1_0() {
+        let (address, handle, serving) = start(streaming_server());
+
+        // HTTP/1.0 keep-alive is honored for sent responses, but a streamed body needs the close
+        let response = exchange(address, "GET / HTTP/1.0\r\nConnection: keep-alive\r\n\r\n");
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(head.contains("\r\nConnection: close"));
+        assert!(!head.contains("Transfer-Encoding"));
+        assert_eq!(body, "Hello, world");
+
+        handle.shutdown();
+        serving.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn streamed_responses_to_head_requests_have_no_body() {
+        let (address, handle, serving) = start(streaming_server());
+
+        let response = exchange(
+            address,
+            "HEAD / HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n",
+        );
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("HTTP/1.1 200 OK"));
+        assert_eq!(body, "");
+
+        handle.shutdown();
+        serving.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn middleware_runs_in_the_order_added_and_can_answer_by_itself() {
+        let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let record = |name: &'static str| {
+            let calls = Arc::clone(&calls);
+            move |_: &mut Context| calls.lock().unwrap().push(name)
+        };
+        let mut server = WebServer::new();
+        server.before("/", record("before"));
+        let (calls_in, calls_out) = (record("around in"), record("around out"));
+        server.around("/", move |context, next| {
+            calls_in(context);
+            next(context);
+            calls_out(context);
+        });
+        server.after("/", record("after"));
+        server.before("/admin", |context| {
+            context.set_status(HttpStatusCode::Unauthorized);
+            context.send_text("Unauthorized");
+        });
+        server.get("/", record("handler"));
+        server.get("/admin", record("admin"));
+        let (address, handle, serving) = start(server);
+
+        let response = exchange(
+            address,
+            "GET / HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n",
+        );
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert_eq!(
+            calls.lock().unwrap().drain(..).collect::<Vec<_>>(),
+            ["before", "around in", "handler", "after", "around out"]
+        );
+
+        let response = exchange(
+            address,
+            "GET /admin HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n",
+        );
+        assert!(response.starts_with("HTTP/1.1 401 Unauthorized"));
+        // The layers outside the one that answered still run, the handler does not
+        assert_eq!(
+            *calls.lock().unwrap(),
+            ["before", "around in", "after", "around out"]
+        );
+
+        handle.shutdown();
+        serving.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn after_layers_can_rewrite_the_status_of_a_completed_response() {
+        let mut server = WebServer::new();
+        server.post("/items", |context| context.send_text("stored"));
+        server.after("/items", |context| {
+            context.set_status(HttpStatusCode::Created)
+        });
+        let (address, handle, serving) = start(server);
+
+        let response = exchange(
+            address,
+            "POST /items HTTP/1.1\r\nHost: a\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        );
+        assert!(response.starts_with("HTTP/1.1 201 Created"));
+        assert!(response.ends_with("stored"));
+
+        handle.shutdown();
+        serving.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn static_files_are_streamed_with_their_length() {
+        let root = std::env::temp_dir().join(format!("webserver-serve-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("digits.txt"), "0123456789").unwrap();
+        let mut server = WebServer::new();
+        server.static_dir("/files", &root);
+        let (address, handle, serving) = start(server);
+        let request = |method: &str, extra: &str| {
+            exchange(
+                address,
+                &format!(
+                    "{method} /files/digits.txt HTTP/1.1\r\nHost: a\r\n{extra}Connection: close\r\n\r\n"
+                ),
+            )
+        };
+
+        let response = request("GET", "");
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("\r\nContent-Length: 10\r\n"));
+        assert!(!response.contains("Transfer-Encoding"));
+        assert!(response.ends_with("\r\n\r\n0123456789"));
+
+        let response = request("HEAD", "");
+        assert!(response.contains("\r\nContent-Length: 10\r\n"));
+        assert!(response.ends_with("\r\n\r\n"));
+
+        let response = request("GET", "Range: bytes=2-4\r\n");
+        assert!(response.starts_with("HTTP/1.1 206 Partial Content"));
+        assert!(response.contains("\r\nContent-Range: bytes 2-4/10\r\n"));
+        assert!(response.ends_with("\r\n\r\n234"));
+
+        handle.shutdown();
+        serving.join().unwrap().unwrap();
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn serves_every_bound_address_until_shut_down() {
+        let mut server = WebServer::new();
+        server.get("/", |context| context.send_text("Hello"));
+        let first = server.bind("127.0.0.1:0").unwrap();
+        let (second, handle, serving) = start(server);
+
+        for address in [first, second] {
+            let response = exchange(
+                address,
+                "GET / HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n",
+            );
+            assert!(response.ends_with("Hello"));
+        }
+
+        // Both accept threads are blocked, and must be woken for run to return
+        handle.shutdown();
+        serving.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn a_shutdown_requested_before_running_ends_it_right_away() {
+        let mut server = WebServer::new();
+        server.bind("127.0.0.1:0").unwrap();
+        server.shutdown_handle().shutdown();
+        server.run().unwrap();
+    }
+
+    #[test]
+    fn run_needs_an_address() {
+        let error = WebServer::new().run().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    }
+}